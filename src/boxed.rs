@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+
+use crate::{Condition, ConditionalMiddleware, Passthrough};
+
+/// A type-erased [`ConditionalMiddleware`] holding a boxed middleware and a boxed
+/// [`Condition`]. This keeps type signatures manageable when several conditionals are built
+/// up dynamically or stored together.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::BoxedConditionalMiddleware;
+/// # use reqwest_conditional_middleware::Passthrough as Inner;
+///
+/// let conditionals = vec![
+///     BoxedConditionalMiddleware::new(
+///         Box::new(Inner),
+///         Box::new(|req: &Request| req.method() == http::Method::GET),
+///     ),
+///     BoxedConditionalMiddleware::new(
+///         Box::new(Inner),
+///         Box::new(|req: &Request| req.url().scheme() == "https"),
+///     ),
+/// ];
+/// ```
+pub struct BoxedConditionalMiddleware {
+    conditional: ConditionalMiddleware<BoxedMiddleware, Box<dyn Condition>, BoxedMiddleware>,
+}
+
+impl BoxedConditionalMiddleware {
+    /// Creates a new wrapped middleware. The condition will be evaluated for each request to
+    /// determine if the wrapped middleware should be run.
+    pub fn new(inner: Box<dyn Middleware>, condition: Box<dyn Condition>) -> Self {
+        Self::with_else(inner, Box::new(Passthrough), condition)
+    }
+
+    /// Creates a new wrapped middleware that runs `inner` when the condition evaluates to
+    /// true and `otherwise` when it evaluates to false
+    pub fn with_else(
+        inner: Box<dyn Middleware>,
+        otherwise: Box<dyn Middleware>,
+        condition: Box<dyn Condition>,
+    ) -> Self {
+        Self {
+            conditional: ConditionalMiddleware::with_else(
+                BoxedMiddleware(inner),
+                BoxedMiddleware(otherwise),
+                condition,
+            ),
+        }
+    }
+}
+
+impl<T, C, E> From<ConditionalMiddleware<T, C, E>> for BoxedConditionalMiddleware
+where
+    T: Middleware,
+    C: Condition,
+    E: Middleware,
+{
+    fn from(conditional: ConditionalMiddleware<T, C, E>) -> Self {
+        Self {
+            conditional: ConditionalMiddleware {
+                inner: BoxedMiddleware(Box::new(conditional.inner)),
+                otherwise: BoxedMiddleware(Box::new(conditional.otherwise)),
+                condition: Box::new(conditional.condition),
+                options: conditional.options,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for BoxedConditionalMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        self.conditional.handle(req, extensions, next).await
+    }
+}
+
+/// Forwards to a boxed middleware, as `Box<dyn Middleware>` does not implement
+/// [`Middleware`] itself
+struct BoxedMiddleware(Box<dyn Middleware>);
+
+#[async_trait]
+impl Middleware for BoxedMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        self.0.handle(req, extensions, next).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{CheckMiddleware, End};
+
+    #[tokio::test]
+    async fn test_boxed() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let other = CheckMiddleware::new();
+        let other_test = other.checker();
+        let conditionals = [
+            BoxedConditionalMiddleware::new(Box::new(check), Box::new(|_req: &Request| true)),
+            ConditionalMiddleware::new(other, |_req: &Request| false).into(),
+        ];
+
+        let mut builder =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap());
+        for conditional in conditionals {
+            builder = builder.with(conditional);
+        }
+        let client = builder.with(End).build();
+
+        client.get("http://localhost").send().await.unwrap();
+
+        assert!(*test.lock().unwrap());
+        assert!(!*other_test.lock().unwrap());
+    }
+}
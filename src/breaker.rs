@@ -0,0 +1,321 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::condition::impl_condition_ops;
+use crate::downstream::{handle_marked, is_own_failure};
+use crate::Condition;
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { reprobe: Instant },
+}
+
+#[derive(Debug)]
+struct Current {
+    state: State,
+    /// Counts state changes, so that outcomes of requests started in an earlier state, such as
+    /// those still in flight when the breaker opened, can be ignored
+    epoch: u64,
+}
+
+impl Current {
+    fn set(&mut self, state: State) {
+        self.state = state;
+        self.epoch += 1;
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    current: Mutex<Current>,
+    threshold: u32,
+    cooldown: Duration,
+}
+
+/// A [`Condition`] that stops a middleware from being run once it has failed `threshold` times
+/// in a row, so that a broken optional middleware degrades gracefully instead of failing every
+/// request. The middleware must be wrapped with [`wrap`](CircuitBreaker::wrap) so that its
+/// outcomes are recorded; only middleware errors count as failures, not errors from the
+/// underlying client. Install [`MarkDownstream`](crate::MarkDownstream) right after the
+/// conditional so that middleware errors from further down the stack, which the wrapped
+/// middleware only passes along, do not count either.
+///
+/// Once open, the breaker skips the middleware for `cooldown`, then lets a single request
+/// through as a probe: the breaker closes again if it succeeds and stays open for another
+/// `cooldown` if it fails. Outcomes of requests that started before the breaker last changed
+/// state are ignored. Clones share the same state. Combine it with
+/// [`bypass_inner_on_error`](crate::ConditionalMiddleware::bypass_inner_on_error) so that the
+/// failing requests themselves are not lost.
+///
+/// ```
+/// use reqwest_conditional_middleware::{CircuitBreaker, ConditionalMiddleware, MarkDownstream};
+/// use std::time::Duration;
+/// # use reqwest_conditional_middleware::Passthrough as Enrichment;
+///
+/// let breaker = CircuitBreaker::new(5, Duration::from_secs(30));
+/// let conditional = ConditionalMiddleware::new(breaker.wrap(Enrichment), breaker.clone())
+///     .bypass_inner_on_error();
+/// let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+///     .with(conditional)
+///     .with(MarkDownstream)
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    shared: Arc<Shared>,
+}
+
+impl_condition_ops!(CircuitBreaker);
+
+impl CircuitBreaker {
+    /// Creates a closed breaker that opens after `threshold` consecutive failures and probes
+    /// again after `cooldown`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is zero.
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        assert!(
+            threshold > 0,
+            "CircuitBreaker requires a threshold of at least 1"
+        );
+        Self {
+            shared: Arc::new(Shared {
+                current: Mutex::new(Current {
+                    state: State::Closed { failures: 0 },
+                    epoch: 0,
+                }),
+                threshold,
+                cooldown,
+            }),
+        }
+    }
+
+    /// Wraps `middleware` so that its failures and successes are recorded by this breaker
+    pub fn wrap<M>(&self, middleware: M) -> Breaker<M>
+    where
+        M: Middleware,
+    {
+        Breaker {
+            inner: middleware,
+            breaker: self.clone(),
+        }
+    }
+
+    /// Returns true if the breaker is currently skipping the middleware, including while a
+    /// probe is in flight
+    pub fn is_open(&self) -> bool {
+        !matches!(
+            self.shared.current.lock().unwrap().state,
+            State::Closed { .. }
+        )
+    }
+
+    fn epoch(&self) -> u64 {
+        self.shared.current.lock().unwrap().epoch
+    }
+
+    /// Records the outcome of a request started at `epoch`
+    fn record(&self, epoch: u64, success: bool) {
+        let mut current = self.shared.current.lock().unwrap();
+        if current.epoch != epoch {
+            return;
+        }
+        match (current.state, success) {
+            (State::Closed { .. }, true) => current.state = State::Closed { failures: 0 },
+            (State::Closed { failures }, false) if failures + 1 < self.shared.threshold => {
+                current.state = State::Closed {
+                    failures: failures + 1,
+                };
+            }
+            // The middleware is not run while open, the outcome is of a request that bypassed it
+            (State::Open { .. }, _) => {}
+            (State::HalfOpen { .. }, true) => current.set(State::Closed { failures: 0 }),
+            (State::Closed { .. } | State::HalfOpen { .. }, false) => {
+                tracing::warn!(cooldown = ?self.shared.cooldown, "circuit breaker opened");
+                current.set(State::Open {
+                    until: Instant::now() + self.shared.cooldown,
+                });
+            }
+        }
+    }
+}
+
+impl Condition for CircuitBreaker {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        let mut current = self.shared.current.lock().unwrap();
+        match current.state {
+            State::Closed { .. } => true,
+            State::Open { until } | State::HalfOpen { reprobe: until }
+                if Instant::now() >= until =>
+            {
+                // Let one request through as a probe. Should its outcome never be recorded,
+                // another probe is allowed after a further cooldown.
+                current.set(State::HalfOpen {
+                    reprobe: Instant::now() + self.shared.cooldown,
+                });
+                true
+            }
+            State::Open { .. } | State::HalfOpen { .. } => false,
+        }
+    }
+}
+
+/// A middleware recording the outcomes of the middleware it wraps in a [`CircuitBreaker`].
+/// Created by [`CircuitBreaker::wrap`].
+#[derive(Clone, Debug)]
+pub struct Breaker<M> {
+    inner: M,
+    breaker: CircuitBreaker,
+}
+
+#[async_trait]
+impl<M> Middleware for Breaker<M>
+where
+    M: Middleware,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let epoch = self.breaker.epoch();
+        let (result, reached) = handle_marked(&self.inner, req, extensions, next).await;
+        self.breaker
+            .record(epoch, !is_own_failure(&result, reached));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::End;
+    use crate::{ConditionalMiddleware, MarkDownstream, Passthrough};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[derive(Clone, Default)]
+    struct Flaky {
+        failing: Arc<AtomicBool>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Middleware for Flaky {
+        async fn handle(
+            &self,
+            req: Request,
+            extensions: &mut Extensions,
+            next: Next<'_>,
+        ) -> Result<Response> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(reqwest_middleware::Error::middleware(
+                    std::io::Error::other("flaky"),
+                ));
+            }
+            next.run(req, extensions).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker() {
+        let flaky = Flaky::default();
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(
+                    ConditionalMiddleware::new(breaker.wrap(flaky.clone()), breaker.clone())
+                        .bypass_inner_on_error(),
+                )
+                .with(End)
+                .build();
+        let send = || async { client.get("http://localhost").send().await.unwrap() };
+
+        flaky.failing.store(true, Ordering::SeqCst);
+        send().await;
+        assert!(!breaker.is_open());
+        send().await;
+        assert!(breaker.is_open());
+        send().await;
+        assert_eq!(2, flaky.calls.load(Ordering::SeqCst));
+
+        // A failed probe opens the breaker again
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        send().await;
+        send().await;
+        assert_eq!(3, flaky.calls.load(Ordering::SeqCst));
+
+        flaky.failing.store(false, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        send().await;
+        assert!(!breaker.is_open());
+        send().await;
+        assert_eq!(5, flaky.calls.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_unrecorded_probe() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let extensions = Extensions::new();
+
+        breaker.record(breaker.epoch(), false);
+        assert!(!breaker.evaluate(&req, &extensions));
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.evaluate(&req, &extensions));
+        assert!(!breaker.evaluate(&req, &extensions));
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_late_outcomes() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let extensions = Extensions::new();
+
+        // A request started while closed succeeds after another one opened the breaker
+        let late = breaker.epoch();
+        breaker.record(breaker.epoch(), false);
+        breaker.record(late, true);
+        assert!(breaker.is_open());
+
+        // Only the probe's outcome closes the breaker
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.evaluate(&req, &extensions));
+        breaker.record(late, true);
+        assert!(breaker.is_open());
+        breaker.record(breaker.epoch(), true);
+        assert!(!breaker.is_open());
+    }
+
+    #[tokio::test]
+    async fn test_downstream_errors() {
+        let flaky = Flaky::default();
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(
+                    breaker.wrap(Passthrough),
+                    breaker.clone(),
+                ))
+                .with(MarkDownstream)
+                .with(flaky.clone())
+                .with(End)
+                .build();
+
+        flaky.failing.store(true, Ordering::SeqCst);
+        assert!(client.get("http://localhost").send().await.is_err());
+        assert!(client.get("http://localhost").send().await.is_err());
+        assert!(!breaker.is_open());
+        assert_eq!(2, flaky.calls.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,206 @@
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::Middleware;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+    AsyncCondition, ConditionalMiddleware, ControlHandle, OnConditionError, Options, Passthrough,
+    Rerun, Timeout,
+};
+
+/// A builder for a [`ConditionalMiddleware`], created by [`ConditionalMiddleware::builder`].
+/// A condition must be set with [`when`][Self::when] before the conditional can be built.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, OnConditionError};
+/// # use reqwest_conditional_middleware::Passthrough as Gzip;
+/// # use reqwest_conditional_middleware::Passthrough as Identity;
+///
+/// let conditional = ConditionalMiddleware::builder(Gzip)
+///     .when(|req: &Request| req.method() == http::Method::POST)
+///     .otherwise(Identity)
+///     .named("gzip-gate")
+///     .on_error(OnConditionError::Skip)
+///     .build();
+/// ```
+pub struct ConditionalMiddlewareBuilder<T, C = (), E = Passthrough> {
+    inner: T,
+    otherwise: E,
+    condition: C,
+    options: Options,
+}
+
+impl<T> ConditionalMiddleware<T, ()>
+where
+    T: Middleware,
+{
+    /// Starts building a conditional that wraps `inner`
+    pub fn builder(inner: T) -> ConditionalMiddlewareBuilder<T> {
+        ConditionalMiddlewareBuilder {
+            inner,
+            otherwise: Passthrough,
+            condition: (),
+            options: Options::default(),
+        }
+    }
+}
+
+impl<T, C, E> ConditionalMiddlewareBuilder<T, C, E>
+where
+    T: Middleware,
+    E: Middleware,
+{
+    /// Sets the condition deciding whether the inner middleware runs
+    pub fn when<D>(self, condition: D) -> ConditionalMiddlewareBuilder<T, D, E>
+    where
+        D: AsyncCondition,
+    {
+        ConditionalMiddlewareBuilder {
+            inner: self.inner,
+            otherwise: self.otherwise,
+            condition,
+            options: self.options,
+        }
+    }
+
+    /// Sets the middleware that runs when the condition evaluates to false. See
+    /// [`ConditionalMiddleware::with_else`].
+    pub fn otherwise<F>(self, otherwise: F) -> ConditionalMiddlewareBuilder<T, C, F>
+    where
+        F: Middleware,
+    {
+        ConditionalMiddlewareBuilder {
+            inner: self.inner,
+            otherwise,
+            condition: self.condition,
+            options: self.options,
+        }
+    }
+
+    /// Sets the name of the conditional. See [`ConditionalMiddleware::with_name`].
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.options.name = Some(name.into());
+        self
+    }
+
+    /// Sets a label describing the condition. See [`ConditionalMiddleware::with_label`].
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.options.label = Some(label.into());
+        self
+    }
+
+    /// Sets a hook run before the condition is evaluated. See
+    /// [`ConditionalMiddleware::before_evaluate`].
+    pub fn before_evaluate<H>(mut self, hook: H) -> Self
+    where
+        H: Fn(&mut Request, &mut Extensions) + Send + Sync + 'static,
+    {
+        self.options.hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets the policy applied when the condition returns an error. See
+    /// [`ConditionalMiddleware::on_error`].
+    pub fn on_error(mut self, policy: OnConditionError) -> Self {
+        self.options.on_error = policy;
+        self
+    }
+
+    /// Bounds the time spent evaluating the condition. See
+    /// [`ConditionalMiddleware::with_timeout`].
+    pub fn timeout(mut self, duration: Duration, fallback: OnConditionError) -> Self {
+        self.options.timeout = Some(Timeout { duration, fallback });
+        self
+    }
+
+    /// Makes the inner middleware best-effort. See
+    /// [`ConditionalMiddleware::bypass_inner_on_error`].
+    pub fn bypass_inner_on_error(mut self) -> Self {
+        self.options.bypass_inner_on_error = true;
+        self
+    }
+
+    /// Installs a handle through which the condition can be overridden at runtime. See
+    /// [`ConditionalMiddleware::with_control`].
+    pub fn control(mut self, handle: ControlHandle) -> Self {
+        self.options.control = Some(handle);
+        self
+    }
+
+    /// Sends the request again through the inner middleware when the response matches. See
+    /// [`ConditionalMiddleware::rerun_when`].
+    pub fn rerun_when<P>(mut self, condition: P) -> Self
+    where
+        P: Fn(&Response) -> bool + Send + Sync + 'static,
+    {
+        self.options.rerun = Some(Rerun {
+            condition: Arc::new(condition),
+            middleware: None,
+        });
+        self
+    }
+
+    /// Sends the request again through `middleware` when the response matches. See
+    /// [`ConditionalMiddleware::rerun_with`].
+    pub fn rerun_with<P, M>(mut self, condition: P, middleware: M) -> Self
+    where
+        P: Fn(&Response) -> bool + Send + Sync + 'static,
+        M: Middleware,
+    {
+        self.options.rerun = Some(Rerun {
+            condition: Arc::new(condition),
+            middleware: Some(Arc::new(middleware)),
+        });
+        self
+    }
+}
+
+impl<T, C, E> ConditionalMiddlewareBuilder<T, C, E>
+where
+    T: Middleware,
+    C: AsyncCondition,
+    E: Middleware,
+{
+    /// Builds the conditional
+    pub fn build(self) -> ConditionalMiddleware<T, C, E> {
+        ConditionalMiddleware {
+            inner: self.inner,
+            otherwise: self.otherwise,
+            condition: self.condition,
+            options: self.options,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{CheckMiddleware, End};
+
+    #[tokio::test]
+    async fn test_builder() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let other = CheckMiddleware::new();
+        let other_test = other.checker();
+        let conditional = ConditionalMiddleware::builder(check)
+            .when(|req: &Request| req.method() == http::Method::POST)
+            .otherwise(other)
+            .named("builder")
+            .build();
+        assert_eq!(Some("builder"), conditional.name());
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(End)
+                .build();
+
+        client.get("http://localhost").send().await.unwrap();
+
+        assert!(!*test.lock().unwrap());
+        assert!(*other_test.lock().unwrap());
+    }
+}
@@ -0,0 +1,264 @@
+use http::header::HeaderName;
+use http::Extensions;
+use reqwest::Request;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::condition::impl_condition_ops;
+use crate::matchers::header_name;
+use crate::rollout::{threshold, Fnv, BUCKETS};
+use crate::Condition;
+
+#[derive(Clone, Debug)]
+enum Key {
+    Host,
+    Header(HeaderName),
+    Extension(fn(&Extensions) -> Option<String>),
+}
+
+fn extension_key<T>(extensions: &Extensions) -> Option<String>
+where
+    T: Display + Send + Sync + 'static,
+{
+    extensions.get::<T>().map(T::to_string)
+}
+
+#[derive(Debug)]
+struct Shared {
+    threshold: AtomicU32,
+    assignments: Mutex<HashMap<String, bool>>,
+}
+
+/// A [`Condition`] enabling a middleware for a canary cohort made of a percentage of hosts or
+/// tenants. Keys are deterministically assigned, by hashing them like a
+/// [`Rollout`](crate::Rollout), the first time they are seen, and keep that assignment for the
+/// lifetime of the condition: changing the percentage only affects keys seen afterwards, so a
+/// host never moves in or out of the cohort in the middle of a session. Requests without a key
+/// never match.
+///
+/// Every key seen is remembered, which suits a bounded set of hosts or tenants rather than
+/// arbitrary user input. Clones share the same assignments.
+///
+/// ```
+/// use reqwest_conditional_middleware::{Canary, ConditionalMiddleware};
+/// # use reqwest_conditional_middleware::Passthrough as NewCompression;
+///
+/// let canary = Canary::by_host(1.0).with_salt("new-compression");
+/// let conditional = ConditionalMiddleware::new(NewCompression, canary.clone());
+///
+/// // Later, to tell which hosts are part of the canary
+/// let hosts = canary.cohort();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Canary {
+    key: Key,
+    salt: String,
+    shared: Arc<Shared>,
+}
+
+impl_condition_ops!(Canary);
+
+impl Canary {
+    fn new(key: Key, percentage: f64) -> Self {
+        Self {
+            key,
+            salt: String::new(),
+            shared: Arc::new(Shared {
+                threshold: AtomicU32::new(threshold(percentage)),
+                assignments: Mutex::default(),
+            }),
+        }
+    }
+
+    /// Assigns `percentage` percent of request hosts to the cohort. Hosts are compared
+    /// case-insensitively.
+    pub fn by_host(percentage: f64) -> Self {
+        Self::new(Key::Host, percentage)
+    }
+
+    /// Assigns `percentage` percent of the values of header `name`, such as a tenant id, to the
+    /// cohort. Requests without the header, or whose value is not valid UTF-8, never match.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    pub fn by_header<N>(name: N, percentage: f64) -> Self
+    where
+        N: TryInto<HeaderName>,
+        N::Error: Debug,
+    {
+        Self::new(Key::Header(header_name(name)), percentage)
+    }
+
+    /// Assigns `percentage` percent of the values of the request extension `T`, such as a
+    /// tenant id inserted by the caller, to the cohort. Values are identified by their
+    /// [`Display`] representation. Requests without the extension never match.
+    pub fn by_extension<T>(percentage: f64) -> Self
+    where
+        T: Display + Send + Sync + 'static,
+    {
+        Self::new(Key::Extension(extension_key::<T>), percentage)
+    }
+
+    /// Hashes `salt` along with each key, so that separate canaries by the same key pick
+    /// different cohorts
+    pub fn with_salt(mut self, salt: impl Into<String>) -> Self {
+        self.salt = salt.into();
+        self
+    }
+
+    /// Changes the percentage of keys assigned to the cohort from now on, from 0 to 100 with a
+    /// resolution of 0.01. Keys that were already seen keep their assignment.
+    pub fn set_percentage(&self, percentage: f64) {
+        self.shared
+            .threshold
+            .store(threshold(percentage), Ordering::Relaxed);
+    }
+
+    /// Returns whether `key` is part of the cohort, or `None` if it has not been seen yet
+    pub fn is_member(&self, key: &str) -> Option<bool> {
+        let key = match self.key {
+            Key::Host => Cow::Owned(key.to_ascii_lowercase()),
+            Key::Header(_) | Key::Extension(_) => Cow::Borrowed(key),
+        };
+        self.shared.assignments.lock().unwrap().get(&*key).copied()
+    }
+
+    /// Returns the keys seen so far that are part of the cohort, sorted
+    pub fn cohort(&self) -> Vec<String> {
+        let mut cohort: Vec<_> = self
+            .shared
+            .assignments
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, member)| **member)
+            .map(|(key, _)| key.clone())
+            .collect();
+        cohort.sort_unstable();
+        cohort
+    }
+
+    fn key<'a>(&self, req: &'a Request, extensions: &Extensions) -> Option<Cow<'a, str>> {
+        match &self.key {
+            Key::Host => req.url().host_str().map(|host| {
+                if host.bytes().any(|byte| byte.is_ascii_uppercase()) {
+                    Cow::Owned(host.to_ascii_lowercase())
+                } else {
+                    Cow::Borrowed(host)
+                }
+            }),
+            Key::Header(name) => req
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(Cow::Borrowed),
+            Key::Extension(key) => key(extensions).map(Cow::Owned),
+        }
+    }
+
+    fn assign(&self, key: &str) -> bool {
+        let mut hasher = Fnv::new();
+        hasher.write(self.salt.as_bytes());
+        hasher.write_u8(0xff);
+        hasher.write(key.as_bytes());
+        hasher.finish() % BUCKETS < u64::from(self.shared.threshold.load(Ordering::Relaxed))
+    }
+}
+
+impl Condition for Canary {
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        let Some(key) = self.key(req, extensions) else {
+            return false;
+        };
+        let mut assignments = self.shared.assignments.lock().unwrap();
+        if let Some(member) = assignments.get(&*key) {
+            return *member;
+        }
+        let member = self.assign(&key);
+        if member {
+            tracing::debug!(key = &*key, "assigned to canary cohort");
+        }
+        assignments.insert(key.into_owned(), member);
+        member
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rollout;
+
+    fn get(url: &str) -> Request {
+        Request::new(http::Method::GET, url.parse().unwrap())
+    }
+
+    #[test]
+    fn test_canary_is_sticky() {
+        let extensions = Extensions::new();
+        let canary = Canary::by_host(100.0);
+
+        assert_eq!(None, canary.is_member("a.example.com"));
+        assert!(canary.evaluate(&get("http://A.example.com"), &extensions));
+
+        // Seen hosts keep their assignment, new ones get the new percentage
+        canary.set_percentage(0.0);
+        assert!(canary
+            .clone()
+            .evaluate(&get("http://a.example.com"), &extensions));
+        assert!(!canary.evaluate(&get("http://b.example.com"), &extensions));
+
+        assert_eq!(Some(true), canary.is_member("A.example.com"));
+        assert_eq!(Some(false), canary.is_member("b.example.com"));
+        assert_eq!(vec!["a.example.com".to_owned()], canary.cohort());
+    }
+
+    #[test]
+    fn test_canary_matches_rollout() {
+        let extensions = Extensions::new();
+        let canary = Canary::by_host(20.0).with_salt("salt");
+        let rollout = Rollout::by_host(20.0).with_salt("salt");
+
+        let mut members = 0;
+        for i in 0..500 {
+            let req = get(&format!("http://host-{i}.example.com"));
+            let member = canary.evaluate(&req, &extensions);
+            assert_eq!(rollout.evaluate(&req, &extensions), member);
+            members += usize::from(member);
+        }
+        assert!((50..150).contains(&members), "{members}");
+        assert_eq!(members, canary.cohort().len());
+    }
+
+    #[test]
+    fn test_canary_by_header_and_extension() {
+        #[derive(Clone)]
+        struct TenantId(u32);
+
+        impl Display for TenantId {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "tenant-{}", self.0)
+            }
+        }
+
+        let by_header = Canary::by_header("x-tenant", 100.0);
+        let by_extension = Canary::by_extension::<TenantId>(100.0);
+        let mut req = get("http://localhost");
+        let mut extensions = Extensions::new();
+
+        assert!(!by_header.evaluate(&req, &extensions));
+        assert!(!by_extension.evaluate(&req, &extensions));
+
+        req.headers_mut()
+            .insert("x-tenant", http::HeaderValue::from_static("acme"));
+        extensions.insert(TenantId(7));
+        assert!(by_header.evaluate(&req, &extensions));
+        assert!(by_extension.evaluate(&req, &extensions));
+        assert_eq!(vec!["acme".to_owned()], by_header.cohort());
+        assert_eq!(Some(true), by_extension.is_member("tenant-7"));
+    }
+}
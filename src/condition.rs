@@ -0,0 +1,918 @@
+//! The [`Condition`] trait decides whether a [`ConditionalMiddleware`][crate::ConditionalMiddleware]
+//! should run its inner middleware for a given request.
+
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// A predicate that is evaluated for each request passing through a
+/// [`ConditionalMiddleware`][crate::ConditionalMiddleware]. When it evaluates to true the
+/// wrapped middleware is run, otherwise the request is passed along to the next middleware.
+///
+/// Any `Fn(&Request) -> bool` closure implements this trait, but implementing it on a named
+/// type allows a condition to be reused across middlewares. Conditions that need to look at
+/// the request [`Extensions`] can be built from a closure with [`from_fn`]. Conditions can be
+/// composed with [`and`][Condition::and], [`or`][Condition::or] and [`not`][Condition::not].
+/// The condition types provided by this crate also support composition with the `&`, `|` and
+/// `!` operators; wrapping a closure with [`from_fn`] lets it take part in these expressions.
+///
+/// ```
+/// use http::Extensions;
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::Condition;
+///
+/// struct IsGet;
+///
+/// impl Condition for IsGet {
+///     fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+///         req.method() == http::Method::GET
+///     }
+/// }
+/// ```
+pub trait Condition: Send + Sync + 'static {
+    /// Returns true if the wrapped middleware should be run for this request. The extensions
+    /// are those of the request currently passing through the middleware stack.
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool;
+
+    /// Returns a condition that is true when both this condition and `other` are true. `other`
+    /// is only evaluated when this condition is true.
+    fn and<O>(self, other: O) -> And<Self, O>
+    where
+        Self: Sized,
+        O: Condition,
+    {
+        And {
+            a: self,
+            b: other,
+            strategy: Strategy::default(),
+        }
+    }
+
+    /// Returns a condition that is true when either this condition or `other` is true. `other`
+    /// is only evaluated when this condition is false.
+    fn or<O>(self, other: O) -> Or<Self, O>
+    where
+        Self: Sized,
+        O: Condition,
+    {
+        Or {
+            a: self,
+            b: other,
+            strategy: Strategy::default(),
+        }
+    }
+
+    /// Returns a condition that is true when this condition is false
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+}
+
+impl<F> Condition for F
+where
+    F: Fn(&Request) -> bool + Send + Sync + 'static,
+{
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        self(req)
+    }
+}
+
+/// Implements `&`, `|` and `!` for a condition type in terms of [`Condition::and`],
+/// [`Condition::or`] and [`Condition::not`]
+macro_rules! impl_condition_ops {
+    ($ty:ident $(<$($param:ident),*>)?) => {
+        impl<$($($param,)*)? Rhs> std::ops::BitAnd<Rhs> for $ty$(<$($param),*>)?
+        where
+            Self: $crate::Condition,
+            Rhs: $crate::Condition,
+        {
+            type Output = $crate::condition::And<Self, Rhs>;
+
+            fn bitand(self, rhs: Rhs) -> Self::Output {
+                $crate::Condition::and(self, rhs)
+            }
+        }
+
+        impl<$($($param,)*)? Rhs> std::ops::BitOr<Rhs> for $ty$(<$($param),*>)?
+        where
+            Self: $crate::Condition,
+            Rhs: $crate::Condition,
+        {
+            type Output = $crate::condition::Or<Self, Rhs>;
+
+            fn bitor(self, rhs: Rhs) -> Self::Output {
+                $crate::Condition::or(self, rhs)
+            }
+        }
+
+        impl$(<$($param),*>)? std::ops::Not for $ty$(<$($param),*>)?
+        where
+            Self: $crate::Condition,
+        {
+            type Output = $crate::condition::Not<Self>;
+
+            fn not(self) -> Self::Output {
+                $crate::Condition::not(self)
+            }
+        }
+    };
+}
+
+pub(crate) use impl_condition_ops;
+
+/// How a composed condition such as [`And`], [`Or`], [`Any`] or [`All`] evaluates the
+/// conditions it is made of
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Stop evaluating as soon as the outcome is known
+    #[default]
+    ShortCircuit,
+    /// Evaluate every condition, emitting a `trace` level [`tracing`] event with the outcome of
+    /// each so that it can be seen why the composed condition matched or did not
+    EvaluateAll,
+}
+
+/// Evaluates every condition, emitting an event with each outcome
+fn evaluate_all<'a>(
+    combinator: &'static str,
+    conditions: impl IntoIterator<Item = &'a dyn Condition>,
+    req: &Request,
+    extensions: &Extensions,
+) -> Vec<bool> {
+    conditions
+        .into_iter()
+        .enumerate()
+        .map(|(index, condition)| {
+            let outcome = condition.evaluate(req, extensions);
+            tracing::trace!(combinator, index, outcome, "evaluated condition");
+            outcome
+        })
+        .collect()
+}
+
+/// A [`Condition`] that is true when both of its conditions are true. Created by
+/// [`Condition::and`] or the `&` operator.
+#[derive(Clone)]
+pub struct And<A, B> {
+    a: A,
+    b: B,
+    strategy: Strategy,
+}
+
+impl_condition_ops!(And<A, B>);
+
+impl<A, B> And<A, B> {
+    /// Sets how the two conditions are evaluated
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+}
+
+impl<A, B> Condition for And<A, B>
+where
+    A: Condition,
+    B: Condition,
+{
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        match self.strategy {
+            Strategy::ShortCircuit => {
+                self.a.evaluate(req, extensions) && self.b.evaluate(req, extensions)
+            }
+            Strategy::EvaluateAll => {
+                let outcomes =
+                    evaluate_all("and", [&self.a as &dyn Condition, &self.b], req, extensions);
+                outcomes.iter().all(|outcome| *outcome)
+            }
+        }
+    }
+}
+
+/// A [`Condition`] that is true when either of its conditions is true. Created by
+/// [`Condition::or`] or the `|` operator.
+#[derive(Clone)]
+pub struct Or<A, B> {
+    a: A,
+    b: B,
+    strategy: Strategy,
+}
+
+impl_condition_ops!(Or<A, B>);
+
+impl<A, B> Or<A, B> {
+    /// Sets how the two conditions are evaluated
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+}
+
+impl<A, B> Condition for Or<A, B>
+where
+    A: Condition,
+    B: Condition,
+{
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        match self.strategy {
+            Strategy::ShortCircuit => {
+                self.a.evaluate(req, extensions) || self.b.evaluate(req, extensions)
+            }
+            Strategy::EvaluateAll => {
+                let outcomes =
+                    evaluate_all("or", [&self.a as &dyn Condition, &self.b], req, extensions);
+                outcomes.iter().any(|outcome| *outcome)
+            }
+        }
+    }
+}
+
+/// A [`Condition`] that negates the condition it wraps. Created by [`not`], [`Condition::not`]
+/// or the `!` operator.
+#[derive(Clone)]
+pub struct Not<C>(C);
+
+impl_condition_ops!(Not<C>);
+
+impl<C> Condition for Not<C>
+where
+    C: Condition,
+{
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        !self.0.evaluate(req, extensions)
+    }
+}
+
+/// Creates a [`Condition`] that is true when `condition` is false. Unlike the `!` operator this
+/// also works on closures and on third-party condition types.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::condition;
+///
+/// let not_health_check = condition::not(|req: &Request| req.url().path() == "/health");
+/// ```
+pub fn not<C>(condition: C) -> Not<C>
+where
+    C: Condition,
+{
+    Not(condition)
+}
+
+impl Condition for Box<dyn Condition> {
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        (**self).evaluate(req, extensions)
+    }
+}
+
+impl<C> Condition for Arc<C>
+where
+    C: Condition + ?Sized,
+{
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        (**self).evaluate(req, extensions)
+    }
+}
+
+/// A [`Condition`] that is true when any of a list of conditions is true. Created by [`any`].
+#[derive(Clone)]
+pub struct Any<C> {
+    conditions: Vec<C>,
+    strategy: Strategy,
+}
+
+impl_condition_ops!(Any<C>);
+
+/// Creates a [`Condition`] that is true when any of `conditions` is true. By default
+/// conditions are evaluated in order, stopping at the first one that is true. An empty list
+/// is never true.
+///
+/// Lists of differing condition types, such as rule sets loaded at runtime, can be combined by
+/// boxing each condition as a `Box<dyn Condition>`.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::{condition, Condition};
+///
+/// let hosts = vec!["api.example.com", "auth.example.com"];
+/// let condition = condition::any(
+///     hosts
+///         .into_iter()
+///         .map(|host| {
+///             Box::new(move |req: &Request| req.url().host_str() == Some(host))
+///                 as Box<dyn Condition>
+///         })
+///         .collect(),
+/// );
+/// ```
+pub fn any<C>(conditions: Vec<C>) -> Any<C>
+where
+    C: Condition,
+{
+    Any {
+        conditions,
+        strategy: Strategy::default(),
+    }
+}
+
+impl<C> Any<C> {
+    /// Sets how the list of conditions is evaluated
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+}
+
+impl<C> Condition for Any<C>
+where
+    C: Condition,
+{
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        match self.strategy {
+            Strategy::ShortCircuit => self
+                .conditions
+                .iter()
+                .any(|condition| condition.evaluate(req, extensions)),
+            Strategy::EvaluateAll => {
+                let conditions = self.conditions.iter().map(|c| c as &dyn Condition);
+                let outcomes = evaluate_all("any", conditions, req, extensions);
+                outcomes.iter().any(|outcome| *outcome)
+            }
+        }
+    }
+}
+
+/// A [`Condition`] that is true when all of a list of conditions are true. Created by [`all`].
+#[derive(Clone)]
+pub struct All<C> {
+    conditions: Vec<C>,
+    strategy: Strategy,
+}
+
+impl_condition_ops!(All<C>);
+
+/// Creates a [`Condition`] that is true when all of `conditions` are true. By default
+/// conditions are evaluated in order, stopping at the first one that is false. An empty list
+/// is always true.
+pub fn all<C>(conditions: Vec<C>) -> All<C>
+where
+    C: Condition,
+{
+    All {
+        conditions,
+        strategy: Strategy::default(),
+    }
+}
+
+impl<C> All<C> {
+    /// Sets how the list of conditions is evaluated
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+}
+
+impl<C> Condition for All<C>
+where
+    C: Condition,
+{
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        match self.strategy {
+            Strategy::ShortCircuit => self
+                .conditions
+                .iter()
+                .all(|condition| condition.evaluate(req, extensions)),
+            Strategy::EvaluateAll => {
+                let conditions = self.conditions.iter().map(|c| c as &dyn Condition);
+                let outcomes = evaluate_all("all", conditions, req, extensions);
+                outcomes.iter().all(|outcome| *outcome)
+            }
+        }
+    }
+}
+
+/// A [`Condition`] that always evaluates to the same value. Created by [`constant`].
+#[derive(Clone, Copy, Debug)]
+pub struct Constant(bool);
+
+impl_condition_ops!(Constant);
+
+/// Creates a [`Condition`] that always evaluates to `value`
+pub fn constant(value: bool) -> Constant {
+    Constant(value)
+}
+
+impl Condition for Constant {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        self.0
+    }
+}
+
+/// A [`Condition`] built from a closure that receives both the request and its extensions.
+/// Created by [`from_fn`].
+#[derive(Clone)]
+pub struct FnCondition<F> {
+    f: F,
+}
+
+impl_condition_ops!(FnCondition<F>);
+
+/// Creates a [`Condition`] from a closure that receives both the request and its
+/// [`Extensions`], so that decisions can use request-scoped metadata
+///
+/// ```
+/// use http::Extensions;
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::condition;
+///
+/// #[derive(Clone)]
+/// struct Tenant(&'static str);
+///
+/// let condition = condition::from_fn(|_req: &Request, extensions: &Extensions| {
+///     extensions.get::<Tenant>().map_or(false, |tenant| tenant.0 == "oxide")
+/// });
+/// ```
+pub fn from_fn<F>(f: F) -> FnCondition<F>
+where
+    F: Fn(&Request, &Extensions) -> bool + Send + Sync + 'static,
+{
+    FnCondition { f }
+}
+
+impl<F> Condition for FnCondition<F>
+where
+    F: Fn(&Request, &Extensions) -> bool + Send + Sync + 'static,
+{
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        (self.f)(req, extensions)
+    }
+}
+
+/// A [`Condition`] that runs a predicate on a typed value in the request extensions. Created
+/// by [`extension`].
+pub struct Extension<T, F> {
+    f: F,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T, F> Clone for Extension<T, F>
+where
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            f: self.f.clone(),
+            _type: PhantomData,
+        }
+    }
+}
+
+impl_condition_ops!(Extension<T, F>);
+
+/// Creates a [`Condition`] that is true when the request extensions hold a value of type `T`
+/// for which `f` returns true. Requests without a `T` never match.
+///
+/// ```
+/// use reqwest_conditional_middleware::condition;
+///
+/// #[derive(Clone)]
+/// struct Tenant(String);
+///
+/// let condition = condition::extension(|tenant: &Tenant| tenant.0 == "oxide");
+/// ```
+pub fn extension<T, F>(f: F) -> Extension<T, F>
+where
+    T: Send + Sync + 'static,
+    F: Fn(&T) -> bool + Send + Sync + 'static,
+{
+    Extension {
+        f,
+        _type: PhantomData,
+    }
+}
+
+impl<T, F> Condition for Extension<T, F>
+where
+    T: Send + Sync + 'static,
+    F: Fn(&T) -> bool + Send + Sync + 'static,
+{
+    fn evaluate(&self, _req: &Request, extensions: &Extensions) -> bool {
+        extensions.get::<T>().is_some_and(|value| (self.f)(value))
+    }
+}
+
+/// The outcome of evaluating a [`TryCondition`] for a request
+#[derive(Debug)]
+pub enum Decision {
+    /// Run the wrapped middleware
+    Run,
+    /// Skip the wrapped middleware
+    Skip,
+    /// Return this response immediately without running the rest of the middleware stack
+    ShortCircuit(Response),
+}
+
+impl Decision {
+    /// A short description of the decision, used in log events
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Decision::Run => "run",
+            Decision::Skip => "skip",
+            Decision::ShortCircuit(_) => "short-circuit",
+        }
+    }
+}
+
+impl From<bool> for Decision {
+    fn from(run: bool) -> Self {
+        if run {
+            Decision::Run
+        } else {
+            Decision::Skip
+        }
+    }
+}
+
+/// A predicate that may fail to produce a [`Decision`]. What happens when evaluation fails is
+/// controlled by the [`OnConditionError`][crate::OnConditionError] policy of the
+/// [`ConditionalMiddleware`][crate::ConditionalMiddleware] it is installed in.
+///
+/// Every [`Condition`] is also a `TryCondition` that never fails and never short-circuits.
+/// Closures can be turned into a `TryCondition` with [`fallible`] and [`decide`].
+pub trait TryCondition: Send + Sync + 'static {
+    /// The error returned when the condition can not be evaluated
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Returns what should be done with this request, or an error if that could not be
+    /// determined
+    fn try_evaluate(&self, req: &Request, extensions: &Extensions)
+        -> Result<Decision, Self::Error>;
+}
+
+impl<C> TryCondition for C
+where
+    C: Condition,
+{
+    type Error = Infallible;
+
+    fn try_evaluate(
+        &self,
+        req: &Request,
+        extensions: &Extensions,
+    ) -> Result<Decision, Self::Error> {
+        Ok(self.evaluate(req, extensions).into())
+    }
+}
+
+/// A predicate that is evaluated asynchronously, for instance by looking up a feature flag in
+/// a remote service. A [`ConditionalMiddleware`][crate::ConditionalMiddleware] can bound the
+/// time spent evaluating it with
+/// [`with_timeout`][crate::ConditionalMiddleware::with_timeout].
+///
+/// Every [`TryCondition`], and so every [`Condition`], is also an `AsyncCondition` that
+/// completes immediately.
+///
+/// ```
+/// use http::Extensions;
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::condition::{AsyncCondition, Decision};
+/// use std::convert::Infallible;
+///
+/// struct FlagLookup;
+///
+/// #[async_trait::async_trait]
+/// impl AsyncCondition for FlagLookup {
+///     type Error = Infallible;
+///
+///     async fn evaluate_async(
+///         &self,
+///         _req: &Request,
+///         _extensions: &Extensions,
+///     ) -> Result<Decision, Self::Error> {
+///         // Ask the flag service
+///         Ok(Decision::Run)
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait AsyncCondition: Send + Sync + 'static {
+    /// The error returned when the condition can not be evaluated
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Returns what should be done with this request, or an error if that could not be
+    /// determined
+    async fn evaluate_async(
+        &self,
+        req: &Request,
+        extensions: &Extensions,
+    ) -> Result<Decision, Self::Error>;
+}
+
+#[async_trait]
+impl<C> AsyncCondition for C
+where
+    C: TryCondition,
+{
+    type Error = C::Error;
+
+    async fn evaluate_async(
+        &self,
+        req: &Request,
+        extensions: &Extensions,
+    ) -> Result<Decision, Self::Error> {
+        self.try_evaluate(req, extensions)
+    }
+}
+
+/// An [`AsyncCondition`] shared behind an [`Arc`], so that one centrally managed condition can
+/// drive any number of conditionals across clients. Created by [`shared`].
+pub type SharedCondition<E = Infallible> = Arc<dyn AsyncCondition<Error = E>>;
+
+#[async_trait]
+impl<E> AsyncCondition for SharedCondition<E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    type Error = E;
+
+    async fn evaluate_async(
+        &self,
+        req: &Request,
+        extensions: &Extensions,
+    ) -> Result<Decision, Self::Error> {
+        (**self).evaluate_async(req, extensions).await
+    }
+}
+
+/// Wraps `condition` in a [`SharedCondition`]. Clones of the returned condition all evaluate
+/// the same underlying condition.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::{condition, ConditionalMiddleware};
+/// # use reqwest_conditional_middleware::Passthrough as Inner;
+///
+/// let condition = condition::shared(|req: &Request| req.method() == http::Method::GET);
+///
+/// let first = ConditionalMiddleware::new(Inner, condition.clone());
+/// let second = ConditionalMiddleware::new(Inner, condition);
+/// ```
+pub fn shared<C>(condition: C) -> SharedCondition<C::Error>
+where
+    C: AsyncCondition,
+{
+    Arc::new(condition)
+}
+
+/// A [`TryCondition`] built from a closure returning a [`Decision`]. Created by [`decide`].
+#[derive(Clone)]
+pub struct Decide<F> {
+    f: F,
+}
+
+/// Creates a [`TryCondition`] from a closure that returns a [`Decision`], allowing the
+/// closure to terminate the middleware stack with a canned response
+///
+/// ```
+/// use http::Extensions;
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::condition::{self, Decision};
+///
+/// let condition = condition::decide(|req: &Request, _extensions: &Extensions| {
+///     if req.url().host_str() == Some("embargoed.example.com") {
+///         let builder = http::Response::builder().status(http::StatusCode::FORBIDDEN);
+///         Decision::ShortCircuit(builder.body("").unwrap().into())
+///     } else {
+///         Decision::Run
+///     }
+/// });
+/// ```
+pub fn decide<F>(f: F) -> Decide<F>
+where
+    F: Fn(&Request, &Extensions) -> Decision + Send + Sync + 'static,
+{
+    Decide { f }
+}
+
+impl<F> TryCondition for Decide<F>
+where
+    F: Fn(&Request, &Extensions) -> Decision + Send + Sync + 'static,
+{
+    type Error = Infallible;
+
+    fn try_evaluate(
+        &self,
+        req: &Request,
+        extensions: &Extensions,
+    ) -> Result<Decision, Self::Error> {
+        Ok((self.f)(req, extensions))
+    }
+}
+
+/// A [`TryCondition`] built from a fallible closure. Created by [`fallible`].
+#[derive(Clone)]
+pub struct Fallible<F> {
+    f: F,
+}
+
+/// Creates a [`TryCondition`] from a closure that returns a `Result<bool, E>` or a
+/// `Result<Decision, E>`
+///
+/// ```
+/// use http::Extensions;
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::{condition, ConditionalMiddleware, OnConditionError};
+/// # struct Inner;
+/// # #[async_trait::async_trait]
+/// # impl reqwest_middleware::Middleware for Inner {
+/// #     async fn handle(
+/// #         &self,
+/// #         req: Request,
+/// #         extensions: &mut Extensions,
+/// #         next: reqwest_middleware::Next<'_>,
+/// #     ) -> reqwest_middleware::Result<reqwest::Response> {
+/// #         next.run(req, extensions).await
+/// #     }
+/// # }
+///
+/// let conditional = ConditionalMiddleware::new(
+///     Inner,
+///     condition::fallible(|req: &Request, _extensions: &Extensions| {
+///         req.headers()
+///             .get("x-enabled")
+///             .map_or(Ok(false), |value| value.to_str().map(|value| value == "1"))
+///     }),
+/// )
+/// .on_error(OnConditionError::Skip);
+/// ```
+pub fn fallible<F, D, E>(f: F) -> Fallible<F>
+where
+    F: Fn(&Request, &Extensions) -> Result<D, E> + Send + Sync + 'static,
+    D: Into<Decision>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    Fallible { f }
+}
+
+impl<F, D, E> TryCondition for Fallible<F>
+where
+    F: Fn(&Request, &Extensions) -> Result<D, E> + Send + Sync + 'static,
+    D: Into<Decision>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    type Error = E;
+
+    fn try_evaluate(
+        &self,
+        req: &Request,
+        extensions: &Extensions,
+    ) -> Result<Decision, Self::Error> {
+        (self.f)(req, extensions).map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn request(method: http::Method, url: &str) -> Request {
+        Request::new(method, url.parse().unwrap())
+    }
+
+    fn is_get(req: &Request) -> bool {
+        req.method() == http::Method::GET
+    }
+
+    fn is_api(req: &Request) -> bool {
+        req.url().host_str() == Some("api.example.com")
+    }
+
+    fn is_internal(req: &Request) -> bool {
+        req.url().path().starts_with("/internal")
+    }
+
+    #[test]
+    fn test_combinators() {
+        let condition = is_get.and(is_api).and(is_internal.not());
+        let extensions = Extensions::new();
+
+        let req = request(http::Method::GET, "http://api.example.com/v1");
+        assert!(condition.evaluate(&req, &extensions));
+
+        let req = request(http::Method::GET, "http://api.example.com/internal/v1");
+        assert!(!condition.evaluate(&req, &extensions));
+
+        let req = request(http::Method::POST, "http://api.example.com/v1");
+        assert!(!condition.evaluate(&req, &extensions));
+
+        let condition = is_get.or(is_api);
+        let req = request(http::Method::POST, "http://api.example.com/v1");
+        assert!(condition.evaluate(&req, &extensions));
+
+        let req = request(http::Method::POST, "http://example.com/v1");
+        assert!(!condition.evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_not() {
+        let condition = not(is_internal);
+        let extensions = Extensions::new();
+
+        let req = request(http::Method::GET, "http://api.example.com/v1");
+        assert!(condition.evaluate(&req, &extensions));
+
+        let req = request(http::Method::GET, "http://api.example.com/internal");
+        assert!(!condition.evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_operators() {
+        let is_get = from_fn(|req: &Request, _extensions: &Extensions| is_get(req));
+        let is_api = from_fn(|req: &Request, _extensions: &Extensions| is_api(req));
+        let is_internal = from_fn(|req: &Request, _extensions: &Extensions| is_internal(req));
+        let condition = is_get & is_api | !is_internal;
+        let extensions = Extensions::new();
+
+        let req = request(http::Method::GET, "http://api.example.com/internal");
+        assert!(condition.evaluate(&req, &extensions));
+
+        let req = request(http::Method::POST, "http://example.com/v1");
+        assert!(condition.evaluate(&req, &extensions));
+
+        let req = request(http::Method::POST, "http://api.example.com/internal");
+        assert!(!condition.evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_any_all() {
+        let conditions = || -> Vec<Box<dyn Condition>> {
+            vec![Box::new(is_get), Box::new(is_api), Box::new(is_internal)]
+        };
+        let extensions = Extensions::new();
+
+        let req = request(http::Method::GET, "http://example.com/v1");
+        assert!(any(conditions()).evaluate(&req, &extensions));
+        assert!(!all(conditions()).evaluate(&req, &extensions));
+
+        let req = request(http::Method::GET, "http://api.example.com/internal");
+        assert!(all(conditions()).evaluate(&req, &extensions));
+
+        let req = request(http::Method::POST, "http://example.com/v1");
+        assert!(!any(conditions()).evaluate(&req, &extensions));
+
+        assert!(!any(Vec::<Box<dyn Condition>>::new()).evaluate(&req, &extensions));
+        assert!(all(Vec::<Box<dyn Condition>>::new()).evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_evaluate_all_strategy() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let counting = |result: bool| {
+            let count = count.clone();
+            move |_req: &Request| {
+                count.fetch_add(1, Ordering::SeqCst);
+                result
+            }
+        };
+        let extensions = Extensions::new();
+        let req = request(http::Method::GET, "http://example.com");
+
+        let condition = counting(false).and(counting(true));
+        assert!(!condition.evaluate(&req, &extensions));
+        assert_eq!(1, count.load(Ordering::SeqCst));
+
+        let condition = condition.with_strategy(Strategy::EvaluateAll);
+        assert!(!condition.evaluate(&req, &extensions));
+        assert_eq!(3, count.load(Ordering::SeqCst));
+
+        let condition =
+            any(vec![counting(true), counting(false)]).with_strategy(Strategy::EvaluateAll);
+        assert!(condition.evaluate(&req, &extensions));
+        assert_eq!(5, count.load(Ordering::SeqCst));
+    }
+
+    #[derive(Clone)]
+    struct Tenant(&'static str);
+
+    #[test]
+    fn test_extension() {
+        let condition = extension(|tenant: &Tenant| tenant.0 == "oxide");
+        let req = request(http::Method::GET, "http://example.com");
+        let mut extensions = Extensions::new();
+
+        assert!(!condition.evaluate(&req, &extensions));
+
+        extensions.insert(Tenant("other"));
+        assert!(!condition.evaluate(&req, &extensions));
+
+        extensions.insert(Tenant("oxide"));
+        assert!(condition.evaluate(&req, &extensions));
+    }
+}
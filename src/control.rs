@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// How a [`ConditionalMiddleware`][crate::ConditionalMiddleware] controlled by a
+/// [`ControlHandle`] decides whether to run its inner middleware
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Override {
+    /// Evaluate the condition as usual
+    #[default]
+    UsePredicate,
+    /// Run the inner middleware for every request without evaluating the condition
+    ForceRun,
+    /// Skip the inner middleware for every request without evaluating the condition
+    ForceSkip,
+}
+
+impl Override {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Override::ForceRun,
+            2 => Override::ForceSkip,
+            _ => Override::UsePredicate,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            Override::UsePredicate => 0,
+            Override::ForceRun => 1,
+            Override::ForceSkip => 2,
+        }
+    }
+}
+
+/// A handle for overriding the condition of the conditionals it is installed in with
+/// [`ConditionalMiddleware::with_control`][crate::ConditionalMiddleware::with_control], giving
+/// operators a way to disable a conditional, force it on, or return it to its predicate at
+/// runtime. Handles are cheap to clone and all clones control the same conditionals, so one
+/// handle installed in several conditionals controls them as a group.
+///
+/// An override set on the handle takes precedence over the [`ForceRun`][crate::ForceRun] and
+/// [`ForceSkip`][crate::ForceSkip] markers of individual requests.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, ControlHandle};
+/// # use reqwest_conditional_middleware::Passthrough as Tracing;
+///
+/// let control = ControlHandle::new();
+/// let conditional = ConditionalMiddleware::new(Tracing, |req: &Request| req.method() == "POST")
+///     .with_control(control.clone());
+///
+/// // Later, while debugging an incident
+/// control.force_run();
+/// // And once it is over
+/// control.reset();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ControlHandle {
+    mode: Arc<AtomicU8>,
+}
+
+impl ControlHandle {
+    /// Creates a handle that initially uses the predicate of the conditionals it controls
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how the controlled conditionals decide, returning the previous override
+    pub fn set(&self, mode: Override) -> Override {
+        Override::from_u8(self.mode.swap(mode.as_u8(), Ordering::Relaxed))
+    }
+
+    /// Returns the current override
+    pub fn get(&self) -> Override {
+        Override::from_u8(self.mode.load(Ordering::Relaxed))
+    }
+
+    /// Makes the controlled conditionals run their inner middleware for every request
+    pub fn force_run(&self) {
+        self.set(Override::ForceRun);
+    }
+
+    /// Disables the controlled conditionals, so that their inner middleware never runs
+    pub fn disable(&self) {
+        self.set(Override::ForceSkip);
+    }
+
+    /// Returns the controlled conditionals to evaluating their condition, enabling them again
+    /// after [`disable`](Self::disable)
+    pub fn reset(&self) {
+        self.set(Override::UsePredicate);
+    }
+}
+
+/// A registry of [`ControlHandle`]s by name, so that every conditional installed under a name,
+/// in any client, can be overridden with a single call. A process-wide registry is available
+/// from [`KillSwitchRegistry::global`], and separate registries can be created and injected
+/// where that is not wanted, such as in tests.
+///
+/// Overriding a name that no conditional uses yet is remembered, and conditionals installed
+/// under that name later start out overridden.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, KillSwitchRegistry};
+/// # use reqwest_conditional_middleware::Passthrough as Tracing;
+///
+/// let registry = KillSwitchRegistry::global();
+/// let conditional = ConditionalMiddleware::new(Tracing, |_req: &Request| true)
+///     .with_control(registry.handle("tracing"));
+///
+/// // Later, from an admin endpoint
+/// registry.disable("tracing");
+/// ```
+#[derive(Debug, Default)]
+pub struct KillSwitchRegistry {
+    handles: Mutex<HashMap<String, ControlHandle>>,
+}
+
+impl KillSwitchRegistry {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the process-wide registry
+    pub fn global() -> &'static KillSwitchRegistry {
+        static GLOBAL: OnceLock<KillSwitchRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(KillSwitchRegistry::new)
+    }
+
+    /// Returns the handle registered under `name`, registering a new one if there is none
+    pub fn handle(&self, name: &str) -> ControlHandle {
+        let mut handles = self.handles.lock().unwrap();
+        handles.entry(name.to_owned()).or_default().clone()
+    }
+
+    /// Sets how the conditionals registered under `name` decide, returning the previous
+    /// override
+    pub fn set(&self, name: &str, mode: Override) -> Override {
+        self.handle(name).set(mode)
+    }
+
+    /// Makes the conditionals registered under `name` run their inner middleware for every
+    /// request
+    pub fn force_run(&self, name: &str) {
+        self.set(name, Override::ForceRun);
+    }
+
+    /// Disables the conditionals registered under `name`
+    pub fn disable(&self, name: &str) {
+        self.set(name, Override::ForceSkip);
+    }
+
+    /// Returns the conditionals registered under `name` to evaluating their condition
+    pub fn reset(&self, name: &str) {
+        self.set(name, Override::UsePredicate);
+    }
+
+    /// Returns the registered names and their current overrides, sorted by name
+    pub fn overrides(&self) -> Vec<(String, Override)> {
+        let mut overrides: Vec<_> = self
+            .handles
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, handle)| (name.clone(), handle.get()))
+            .collect();
+        overrides.sort();
+        overrides
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_handle() {
+        let control = ControlHandle::new();
+        let clone = control.clone();
+        assert_eq!(Override::UsePredicate, control.get());
+
+        clone.force_run();
+        assert_eq!(Override::ForceRun, control.get());
+        control.disable();
+        assert_eq!(Override::ForceSkip, clone.get());
+        assert_eq!(Override::ForceSkip, control.set(Override::ForceRun));
+        control.reset();
+        assert_eq!(Override::UsePredicate, clone.get());
+    }
+
+    #[test]
+    fn test_kill_switch_registry() {
+        let registry = KillSwitchRegistry::new();
+        let tracing = registry.handle("tracing");
+        let retries = registry.handle("retries");
+
+        registry.disable("tracing");
+        assert_eq!(Override::ForceSkip, tracing.get());
+        assert_eq!(Override::ForceSkip, registry.handle("tracing").get());
+        assert_eq!(Override::UsePredicate, retries.get());
+
+        registry.force_run("signing");
+        assert_eq!(Override::ForceRun, registry.handle("signing").get());
+        assert_eq!(
+            vec![
+                ("retries".to_owned(), Override::UsePredicate),
+                ("signing".to_owned(), Override::ForceRun),
+                ("tracing".to_owned(), Override::ForceSkip),
+            ],
+            registry.overrides()
+        );
+
+        registry.reset("tracing");
+        assert_eq!(Override::UsePredicate, tracing.get());
+    }
+}
@@ -0,0 +1,74 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+
+/// What [`MarkDownstream`] saw of the rest of the stack, left in the extensions for the
+/// middleware wrapping the one above it
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Reached {
+    /// The last request through the rest of the stack returned a middleware error
+    pub(crate) failed: bool,
+}
+
+/// A middleware marking where the rest of the stack starts, so that the errors of a middleware
+/// can be told apart from the errors it passes along from further down, and a middleware
+/// failing before passing the request on from one failing after. Install it right after a
+/// [`ConditionalMiddleware`](crate::ConditionalMiddleware) using
+/// [`bypass_inner_on_error`](crate::ConditionalMiddleware::bypass_inner_on_error), or wrapping
+/// its middleware with [`CircuitBreaker::wrap`](crate::CircuitBreaker::wrap) or
+/// [`ErrorRate::wrap`](crate::ErrorRate::wrap).
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, MarkDownstream};
+/// # use reqwest_conditional_middleware::Passthrough as Telemetry;
+///
+/// let telemetry = ConditionalMiddleware::new(Telemetry, |_req: &Request| true);
+/// let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+///     .with(telemetry.bypass_inner_on_error())
+///     .with(MarkDownstream)
+///     .build();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MarkDownstream;
+
+#[async_trait]
+impl Middleware for MarkDownstream {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let result = next.run(req, extensions).await;
+        let failed = matches!(&result, Err(err) if err.is_middleware());
+        extensions.insert(Reached { failed });
+        result
+    }
+}
+
+/// Runs `inner`, returning its result along with what a [`MarkDownstream`] below it saw, if
+/// the request got that far. The mark is left in place for the middleware further out, which
+/// the request passed through as well.
+pub(crate) async fn handle_marked<M>(
+    inner: &M,
+    req: Request,
+    extensions: &mut Extensions,
+    next: Next<'_>,
+) -> (Result<Response>, Option<Reached>)
+where
+    M: Middleware + ?Sized,
+{
+    // A mark left by an earlier run, for instance before a rerun, is stale
+    extensions.remove::<Reached>();
+    let result = inner.handle(req, extensions, next).await;
+    let reached = extensions.get::<Reached>().copied();
+    (result, reached)
+}
+
+/// Returns true if `result` is a middleware error of the wrapped middleware itself, rather than
+/// one passed along from the rest of the stack
+pub(crate) fn is_own_failure(result: &Result<Response>, reached: Option<Reached>) -> bool {
+    matches!(result, Err(err) if err.is_middleware()) && !reached.is_some_and(|r| r.failed)
+}
@@ -0,0 +1,132 @@
+use http::Extensions;
+use reqwest::Request;
+use std::ffi::{OsStr, OsString};
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// Returns true if an environment variable is set to a value other than an empty string, `0`,
+/// `false`, `no` or `off`, compared case-insensitively
+fn is_truthy(value: Option<OsString>) -> bool {
+    value.is_some_and(|value| {
+        let value = value.to_string_lossy();
+        let value = value.trim();
+        !(value.is_empty()
+            || ["0", "false", "no", "off"]
+                .iter()
+                .any(|falsy| value.eq_ignore_ascii_case(falsy)))
+    })
+}
+
+/// An environment lookup function, returning the value of the variable it is given
+type Lookup = fn(&OsStr) -> Option<OsString>;
+
+#[derive(Clone, Debug)]
+enum Mode {
+    Cached(bool),
+    Live(OsString, Lookup),
+}
+
+/// A [`Condition`] that is true when an environment variable is set to a truthy value, for
+/// quick operational overrides such as `DISABLE_RETRY_MW=1`. Any value other than an empty
+/// string, `0`, `false`, `no` or `off` (compared case-insensitively) is truthy.
+///
+/// ```
+/// use reqwest_conditional_middleware::{condition, ConditionalMiddleware, EnvVar};
+/// # use reqwest_conditional_middleware::Passthrough as Retry;
+///
+/// let conditional = ConditionalMiddleware::new(
+///     Retry,
+///     condition::not(EnvVar::cached("DISABLE_RETRY_MW")),
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct EnvVar(Mode);
+
+impl_condition_ops!(EnvVar);
+
+impl EnvVar {
+    /// Reads the variable `name` once, now, and always evaluates to the value it had
+    pub fn cached(name: impl AsRef<OsStr>) -> Self {
+        Self(Mode::Cached(is_truthy(std::env::var_os(name))))
+    }
+
+    #[cfg(test)]
+    fn cached_with(name: &OsStr, lookup: Lookup) -> Self {
+        Self(Mode::Cached(is_truthy(lookup(name))))
+    }
+
+    /// Reads the variable `name` on every evaluation, so that changes made by the process
+    /// after the client is built take effect. Reading the environment takes a process-wide
+    /// lock and copies the value, so prefer [`EnvVar::cached`] on hot paths.
+    pub fn live(name: impl Into<OsString>) -> Self {
+        Self(Mode::Live(name.into(), |name| std::env::var_os(name)))
+    }
+
+    #[cfg(test)]
+    fn live_with(name: OsString, lookup: Lookup) -> Self {
+        Self(Mode::Live(name, lookup))
+    }
+}
+
+impl Condition for EnvVar {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        match &self.0 {
+            Mode::Cached(enabled) => *enabled,
+            Mode::Live(name, lookup) => is_truthy(lookup(name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_is_truthy() {
+        for value in ["1", "true", "yes", "on", "anything"] {
+            assert!(is_truthy(Some(value.into())), "{value}");
+        }
+        for value in ["", " ", "0", "FALSE", "No", "off"] {
+            assert!(!is_truthy(Some(value.into())), "{value}");
+        }
+        assert!(!is_truthy(None));
+    }
+
+    #[test]
+    fn test_env_var() {
+        // Setting variables of the real environment races with other tests reading it
+        static VALUE: Mutex<Option<&str>> = Mutex::new(None);
+        fn lookup(name: &OsStr) -> Option<OsString> {
+            assert_eq!(name, "DISABLE_MW");
+            VALUE.lock().unwrap().map(OsString::from)
+        }
+
+        let extensions = Extensions::new();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+
+        *VALUE.lock().unwrap() = Some("1");
+        let cached = EnvVar::cached_with("DISABLE_MW".as_ref(), lookup);
+        let live = EnvVar::live_with("DISABLE_MW".into(), lookup);
+        assert!(cached.evaluate(&req, &extensions));
+        assert!(live.evaluate(&req, &extensions));
+
+        *VALUE.lock().unwrap() = None;
+        assert!(cached.evaluate(&req, &extensions));
+        assert!(!live.evaluate(&req, &extensions));
+
+        *VALUE.lock().unwrap() = Some("off");
+        assert!(!live.evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_env_var_missing() {
+        let extensions = Extensions::new();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let name = "REQWEST_CONDITIONAL_MIDDLEWARE_TEST_UNSET_ENV_VAR";
+
+        assert!(!EnvVar::cached(name).evaluate(&req, &extensions));
+        assert!(!EnvVar::live(name).evaluate(&req, &extensions));
+    }
+}
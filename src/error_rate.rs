@@ -0,0 +1,258 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::condition::impl_condition_ops;
+use crate::downstream::{handle_marked, is_own_failure};
+use crate::Condition;
+
+/// The number of buckets the window is divided into
+const BUCKETS: usize = 10;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Bucket {
+    index: u64,
+    total: u32,
+    errors: u32,
+}
+
+#[derive(Debug)]
+struct Shared {
+    bucket_len: Duration,
+    origin: Instant,
+    buckets: Mutex<[Bucket; BUCKETS]>,
+}
+
+impl Shared {
+    fn current_index(&self) -> u64 {
+        (self.origin.elapsed().as_nanos() / self.bucket_len.as_nanos().max(1)) as u64
+    }
+}
+
+/// A [`Condition`] that disables a middleware while the rate of errors it returns over a
+/// sliding window exceeds a threshold. The middleware must be wrapped with
+/// [`wrap`](ErrorRate::wrap) so that its outcomes are recorded; only middleware errors count,
+/// not errors from the underlying client. Install [`MarkDownstream`](crate::MarkDownstream)
+/// right after the conditional so that middleware errors from further down the stack, which
+/// the wrapped middleware only passes along, are not counted against it.
+///
+/// Rates are only acted on once the window holds at least
+/// [`with_min_samples`](ErrorRate::with_min_samples) outcomes, 10 by default. While the
+/// middleware is disabled no outcomes are recorded, so it is enabled again once its errors have
+/// aged out of the window and the rate is measured afresh. Clones share the same window.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, ErrorRate, MarkDownstream};
+/// use std::time::Duration;
+/// # use reqwest_conditional_middleware::Passthrough as Enrichment;
+///
+/// // Disable enrichment while more than 5% of its calls fail over the last minute
+/// let errors = ErrorRate::new(0.05, Duration::from_secs(60));
+/// let conditional = ConditionalMiddleware::new(errors.wrap(Enrichment), errors.clone())
+///     .bypass_inner_on_error();
+/// let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+///     .with(conditional)
+///     .with(MarkDownstream)
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ErrorRate {
+    threshold: f64,
+    min_samples: u32,
+    shared: Arc<Shared>,
+}
+
+impl_condition_ops!(ErrorRate);
+
+impl ErrorRate {
+    /// Creates a condition disabling the middleware while more than `threshold`, a fraction
+    /// between 0 and 1, of its calls over the last `window` failed
+    pub fn new(threshold: f64, window: Duration) -> Self {
+        Self {
+            threshold,
+            min_samples: 10,
+            shared: Arc::new(Shared {
+                bucket_len: window / BUCKETS as u32,
+                origin: Instant::now(),
+                buckets: Mutex::new([Bucket::default(); BUCKETS]),
+            }),
+        }
+    }
+
+    /// Sets how many outcomes the window must hold before the rate is acted on
+    pub fn with_min_samples(mut self, min_samples: u32) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Wraps `middleware` so that its outcomes are recorded in this window
+    pub fn wrap<M>(&self, middleware: M) -> ErrorRateTracked<M>
+    where
+        M: Middleware,
+    {
+        ErrorRateTracked {
+            inner: middleware,
+            errors: self.clone(),
+        }
+    }
+
+    /// Returns the fraction of recorded calls that failed within the window and how many calls
+    /// were recorded
+    pub fn rate(&self) -> (f64, u32) {
+        let (total, errors) = self.counts();
+        let rate = if total == 0 {
+            0.0
+        } else {
+            f64::from(errors) / f64::from(total)
+        };
+        (rate, total)
+    }
+
+    fn counts(&self) -> (u32, u32) {
+        let current = self.shared.current_index();
+        let buckets = self.shared.buckets.lock().unwrap();
+        buckets
+            .iter()
+            .filter(|bucket| bucket.total > 0 && current - bucket.index < BUCKETS as u64)
+            .fold((0, 0), |(total, errors), bucket| {
+                (total + bucket.total, errors + bucket.errors)
+            })
+    }
+
+    fn record(&self, failed: bool) {
+        let index = self.shared.current_index();
+        let mut buckets = self.shared.buckets.lock().unwrap();
+        let bucket = &mut buckets[(index % BUCKETS as u64) as usize];
+        if bucket.index != index {
+            *bucket = Bucket {
+                index,
+                ..Bucket::default()
+            };
+        }
+        bucket.total += 1;
+        bucket.errors += u32::from(failed);
+    }
+}
+
+impl Condition for ErrorRate {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        let (rate, total) = self.rate();
+        total < self.min_samples || rate <= self.threshold
+    }
+}
+
+/// A middleware recording the outcomes of the middleware it wraps in an [`ErrorRate`]. Created
+/// by [`ErrorRate::wrap`].
+#[derive(Clone, Debug)]
+pub struct ErrorRateTracked<M> {
+    inner: M,
+    errors: ErrorRate,
+}
+
+#[async_trait]
+impl<M> Middleware for ErrorRateTracked<M>
+where
+    M: Middleware,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let (result, reached) = handle_marked(&self.inner, req, extensions, next).await;
+        self.errors.record(is_own_failure(&result, reached));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::End;
+    use crate::{ConditionalMiddleware, MarkDownstream};
+    use reqwest_middleware::Error;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Fails while `self.0` is set, without passing the request on
+    struct Failing(Arc<AtomicBool>);
+
+    #[async_trait]
+    impl Middleware for Failing {
+        async fn handle(
+            &self,
+            req: Request,
+            extensions: &mut Extensions,
+            next: Next<'_>,
+        ) -> Result<Response> {
+            if self.0.load(Ordering::Relaxed) {
+                return Err(Error::middleware(std::fmt::Error));
+            }
+            next.run(req, extensions).await
+        }
+    }
+
+    #[test]
+    fn test_error_rate() {
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let extensions = Extensions::new();
+        let errors = ErrorRate::new(0.25, Duration::from_millis(500)).with_min_samples(4);
+
+        for _ in 0..3 {
+            errors.record(true);
+        }
+        assert!(errors.evaluate(&req, &extensions), "too few samples");
+        errors.record(false);
+        assert_eq!((0.75, 4), errors.rate());
+        assert!(!errors.evaluate(&req, &extensions));
+
+        for _ in 0..8 {
+            errors.record(false);
+        }
+        assert!(errors.evaluate(&req, &extensions));
+
+        errors.record(true);
+        errors.record(true);
+        assert!(!errors.evaluate(&req, &extensions));
+
+        // Errors age out of the window
+        std::thread::sleep(Duration::from_millis(600));
+        assert_eq!((0.0, 0), errors.rate());
+        assert!(errors.evaluate(&req, &extensions));
+    }
+
+    #[tokio::test]
+    async fn test_downstream_errors() {
+        let errors = ErrorRate::new(0.25, Duration::from_secs(60)).with_min_samples(4);
+        let inner_failing = Arc::new(AtomicBool::new(false));
+        let downstream_failing = Arc::new(AtomicBool::new(true));
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(
+                    errors.wrap(Failing(inner_failing.clone())),
+                    errors.clone(),
+                ))
+                .with(MarkDownstream)
+                .with(Failing(downstream_failing.clone()))
+                .with(End)
+                .build();
+
+        // Downstream failures are not the wrapped middleware's
+        for _ in 0..4 {
+            assert!(client.get("http://localhost").send().await.is_err());
+        }
+        assert_eq!((0.0, 4), errors.rate());
+
+        inner_failing.store(true, Ordering::Relaxed);
+        downstream_failing.store(false, Ordering::Relaxed);
+        for _ in 0..2 {
+            assert!(client.get("http://localhost").send().await.is_err());
+        }
+        assert_eq!((2.0 / 6.0, 6), errors.rate());
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        assert!(!errors.evaluate(&req, &Extensions::new()));
+    }
+}
@@ -0,0 +1,78 @@
+use http::Extensions;
+use reqwest::Request;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] that is true for every `n`th request it evaluates: the first one, then the
+/// `n + 1`th, and so on. Unlike random sampling the outcome is deterministic, which suits
+/// periodic probes and sampled verbose logging. Clones share the same counter.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, EveryNth};
+/// # use reqwest_conditional_middleware::Passthrough as VerboseLogging;
+///
+/// let conditional = ConditionalMiddleware::new(VerboseLogging, EveryNth::new(100));
+/// ```
+#[derive(Clone, Debug)]
+pub struct EveryNth {
+    n: u64,
+    count: Arc<AtomicU64>,
+}
+
+impl_condition_ops!(EveryNth);
+
+impl EveryNth {
+    /// Creates a condition true for one in every `n` requests
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn new(n: u64) -> Self {
+        assert!(n > 0, "EveryNth requires n to be at least 1");
+        Self {
+            n,
+            count: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl Condition for EveryNth {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        self.count
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(self.n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_nth() {
+        let extensions = Extensions::new();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let condition = EveryNth::new(3);
+        let clone = condition.clone();
+
+        let outcomes: Vec<_> = (0..7)
+            .map(|i| {
+                let condition = if i % 2 == 0 { &condition } else { &clone };
+                condition.evaluate(&req, &extensions)
+            })
+            .collect();
+        assert_eq!(vec![true, false, false, true, false, false, true], outcomes);
+
+        let always = EveryNth::new(1);
+        assert!((0..5).all(|_| always.evaluate(&req, &extensions)));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1")]
+    fn test_every_nth_zero() {
+        EveryNth::new(0);
+    }
+}
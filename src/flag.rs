@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::Request;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use crate::{AsyncCondition, Decision};
+
+/// A source of feature flags, such as a flag service client, consulted by a [`FlagCondition`]
+/// so that middleware gating can be managed with existing flag tooling. Providers receive the
+/// request being evaluated so that flags can target hosts, paths or headers.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::FlagProvider;
+///
+/// struct Flags;
+///
+/// #[async_trait::async_trait]
+/// impl FlagProvider for Flags {
+///     async fn is_enabled(&self, flag: &str, req: &Request) -> bool {
+///         // Ask the flag service
+///         flag == "retries" && req.method() == http::Method::GET
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait FlagProvider: Send + Sync + 'static {
+    /// Returns true if `flag` is enabled for `req`. Providers decide what a flag that can not
+    /// be evaluated, for instance because the flag service is unreachable, resolves to.
+    async fn is_enabled(&self, flag: &str, req: &Request) -> bool;
+}
+
+#[async_trait]
+impl<P> FlagProvider for Arc<P>
+where
+    P: FlagProvider + ?Sized,
+{
+    async fn is_enabled(&self, flag: &str, req: &Request) -> bool {
+        (**self).is_enabled(flag, req).await
+    }
+}
+
+/// An [`AsyncCondition`] that is true when a feature flag is enabled for the request, as told
+/// by a [`FlagProvider`]. Share one provider between several conditions by wrapping it in an
+/// [`Arc`].
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, FlagCondition};
+/// # use reqwest_conditional_middleware::Passthrough as Retry;
+/// # struct Flags;
+/// # #[async_trait::async_trait]
+/// # impl reqwest_conditional_middleware::FlagProvider for Flags {
+/// #     async fn is_enabled(&self, _flag: &str, _req: &reqwest::Request) -> bool { true }
+/// # }
+///
+/// let conditional = ConditionalMiddleware::new(Retry, FlagCondition::new(Flags, "retries"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FlagCondition<P> {
+    provider: P,
+    flag: String,
+}
+
+impl<P> FlagCondition<P>
+where
+    P: FlagProvider,
+{
+    /// Creates a condition that is true when `flag` is enabled according to `provider`
+    pub fn new(provider: P, flag: impl Into<String>) -> Self {
+        Self {
+            provider,
+            flag: flag.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl<P> AsyncCondition for FlagCondition<P>
+where
+    P: FlagProvider,
+{
+    type Error = Infallible;
+
+    async fn evaluate_async(
+        &self,
+        req: &Request,
+        _extensions: &Extensions,
+    ) -> Result<Decision, Self::Error> {
+        Ok(self.provider.is_enabled(&self.flag, req).await.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    struct Enabled(HashSet<&'static str>);
+
+    #[async_trait]
+    impl FlagProvider for Enabled {
+        async fn is_enabled(&self, flag: &str, req: &Request) -> bool {
+            self.0.contains(flag) && req.url().host_str() == Some("localhost")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flag_condition() {
+        let extensions = Extensions::new();
+        let provider = Arc::new(Enabled(HashSet::from(["retries"])));
+        let retries = FlagCondition::new(provider.clone(), "retries");
+        let tracing = FlagCondition::new(provider, "tracing");
+
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let decision = retries.evaluate_async(&req, &extensions).await.unwrap();
+        assert!(matches!(decision, Decision::Run));
+        let decision = tracing.evaluate_async(&req, &extensions).await.unwrap();
+        assert!(matches!(decision, Decision::Skip));
+
+        let req = Request::new(http::Method::GET, "http://example.com".parse().unwrap());
+        let decision = retries.evaluate_async(&req, &extensions).await.unwrap();
+        assert!(matches!(decision, Decision::Skip));
+    }
+}
@@ -0,0 +1,160 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// Decrements the in-flight count when a request completes, including when its future is
+/// dropped
+struct Guard<'a>(&'a AtomicUsize);
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A [`Condition`] that is true while at most a number of requests are in flight, so that a
+/// heavyweight middleware is shed under load. Requests are counted by wrapping the conditional
+/// with [`wrap`](InFlight::wrap): a request is in flight from when it enters the conditional
+/// until its response, or error, leaves it. The request being evaluated is itself in flight.
+/// Clones share the same count.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, InFlight};
+/// # use reqwest_conditional_middleware::Passthrough as Heavyweight;
+///
+/// let in_flight = InFlight::at_most(64);
+/// let conditional = in_flight.wrap(ConditionalMiddleware::new(Heavyweight, in_flight.clone()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct InFlight {
+    max: usize,
+    count: Arc<AtomicUsize>,
+}
+
+impl_condition_ops!(InFlight);
+
+impl InFlight {
+    /// Creates a condition true while at most `max` requests, including the one being
+    /// evaluated, are in flight
+    pub fn at_most(max: usize) -> Self {
+        Self {
+            max,
+            count: Arc::default(),
+        }
+    }
+
+    /// Wraps `middleware`, usually the conditional using this condition, so that the requests
+    /// passing through it are counted
+    pub fn wrap<M>(&self, middleware: M) -> InFlightTracked<M>
+    where
+        M: Middleware,
+    {
+        InFlightTracked {
+            inner: middleware,
+            count: self.count.clone(),
+        }
+    }
+
+    /// Returns the number of requests currently in flight
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+impl Condition for InFlight {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        self.count() <= self.max
+    }
+}
+
+/// A middleware counting the requests in flight through the middleware it wraps for an
+/// [`InFlight`]. Created by [`InFlight::wrap`].
+#[derive(Clone, Debug)]
+pub struct InFlightTracked<M> {
+    inner: M,
+    count: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl<M> Middleware for InFlightTracked<M>
+where
+    M: Middleware,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let _guard = Guard(&self.count);
+        self.inner.handle(req, extensions, next).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{CheckMiddleware, End};
+    use crate::ConditionalMiddleware;
+    use std::time::Duration;
+    use tokio::sync::Barrier;
+
+    /// Waits at a barrier before passing the request along, holding requests in flight
+    struct Hold(Arc<Barrier>);
+
+    #[async_trait]
+    impl Middleware for Hold {
+        async fn handle(
+            &self,
+            req: Request,
+            extensions: &mut Extensions,
+            next: Next<'_>,
+        ) -> Result<Response> {
+            self.0.wait().await;
+            next.run(req, extensions).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_flight() {
+        let in_flight = InFlight::at_most(1);
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let barrier = Arc::new(Barrier::new(3));
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(in_flight.wrap(ConditionalMiddleware::new(check, in_flight.clone())))
+                .with(Hold(barrier.clone()))
+                .with(End)
+                .build();
+
+        // The first request runs the check middleware, the second one finds it busy
+        let first = tokio::spawn({
+            let client = client.clone();
+            async move { client.get("http://localhost").send().await.unwrap() }
+        });
+        while in_flight.count() == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let second = tokio::spawn({
+            let client = client.clone();
+            async move { client.get("http://localhost").send().await.unwrap() }
+        });
+        while in_flight.count() < 2 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        barrier.wait().await;
+        first.await.unwrap();
+        second.await.unwrap();
+        assert_eq!(0, in_flight.count());
+        assert!(*test.lock().unwrap());
+    }
+}
@@ -0,0 +1,446 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// The number of slices the window is divided into, each expiring as a whole
+const SLICES: usize = 10;
+
+/// The number of histogram bins, four per doubling from 1µs, up to about 16s. A p99 read from
+/// the histogram is within 25% above the true value.
+const BINS: usize = 96;
+
+/// How many samples are recorded between checks of the p99
+const CHECK_EVERY: usize = 16;
+
+/// Tells budgets apart in [`DownstreamTime`]
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Time spent below each wrapped middleware, by budget id, recorded in the extensions by
+/// [`MeasureDownstream`]. Keeping them apart lets budgets be stacked, each measuring only its
+/// own middleware.
+#[derive(Clone, Debug, Default)]
+struct DownstreamTime(Vec<(usize, Duration)>);
+
+impl DownstreamTime {
+    fn take(extensions: &mut Extensions, id: usize) -> Duration {
+        let Some(DownstreamTime(times)) = extensions.get_mut::<DownstreamTime>() else {
+            return Duration::ZERO;
+        };
+        match times.iter().position(|(budget, _)| *budget == id) {
+            Some(index) => times.swap_remove(index).1,
+            None => Duration::ZERO,
+        }
+    }
+
+    fn add(extensions: &mut Extensions, id: usize, time: Duration) {
+        let DownstreamTime(times) = extensions.get_or_insert_default::<DownstreamTime>();
+        match times.iter_mut().find(|(budget, _)| *budget == id) {
+            Some((_, total)) => *total += time,
+            None => times.push((id, time)),
+        }
+    }
+}
+
+/// Returns the histogram bin of `overhead`
+fn bin(overhead: Duration) -> usize {
+    let micros = u64::try_from(overhead.as_micros())
+        .unwrap_or(u64::MAX)
+        .max(1);
+    let log = 63 - micros.leading_zeros() as usize;
+    // The two bits after the leading one split each doubling in four
+    let quarter = if log >= 2 {
+        (micros >> (log - 2)) & 3
+    } else {
+        (micros << (2 - log)) & 3
+    };
+    (log * 4 + quarter as usize).min(BINS - 1)
+}
+
+/// Returns the upper bound of histogram bin `bin`
+fn upper_bound(bin: usize) -> Duration {
+    let (log, quarter) = (bin / 4, bin as u64 % 4);
+    Duration::from_micros(((5 + quarter) << log).div_ceil(4))
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Slice {
+    index: u64,
+    counts: [u32; BINS],
+}
+
+#[derive(Debug)]
+struct State {
+    origin: Instant,
+    slices: [Slice; SLICES],
+    unchecked: usize,
+    disabled_until: Option<Instant>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            origin: Instant::now(),
+            slices: [Slice {
+                index: 0,
+                counts: [0; BINS],
+            }; SLICES],
+            unchecked: 0,
+            disabled_until: None,
+        }
+    }
+}
+
+/// Returns the upper bound of the bin holding the 99th percentile of the samples in `slices`
+/// that are still in the window ending in slice `current`, and the number of those samples
+fn p99(slices: &[Slice; SLICES], current: u64) -> (Duration, u64) {
+    let mut counts = [0u64; BINS];
+    for slice in slices
+        .iter()
+        .filter(|slice| current - slice.index < SLICES as u64)
+    {
+        for (total, count) in counts.iter_mut().zip(slice.counts) {
+            *total += u64::from(count);
+        }
+    }
+    let total: u64 = counts.iter().sum();
+    let rank = (total * 99).div_ceil(100);
+    let mut seen = 0;
+    for (bin, count) in counts.iter().enumerate() {
+        seen += count;
+        if seen >= rank && *count > 0 {
+            return (upper_bound(bin), total);
+        }
+    }
+    (Duration::ZERO, total)
+}
+
+/// A [`Condition`] that disables a middleware while the rolling p99 of the latency it adds
+/// exceeds a budget. The middleware must be wrapped with [`wrap`](LatencyBudget::wrap) so that
+/// its latency is measured, and [`downstream`](LatencyBudget::downstream) installed right
+/// after the conditional so that the time spent in the rest of the stack and on the network is
+/// not attributed to it. Without the latter the whole time spent below the middleware counts.
+///
+/// The p99 is estimated from a histogram of the calls of the last `window`, slightly
+/// overestimating it, once at least [`with_min_samples`](LatencyBudget::with_min_samples)
+/// have been measured, 100 by default. When it exceeds the budget the middleware is disabled for a
+/// `window`, after which it is enabled again and measured afresh. Recording a call takes
+/// constant time, and the p99 is only checked every few calls. Clones share the same
+/// measurements.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, LatencyBudget};
+/// use std::time::Duration;
+/// # use reqwest_conditional_middleware::Passthrough as Enrichment;
+///
+/// let budget = LatencyBudget::new(Duration::from_millis(20), Duration::from_secs(60));
+/// let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+///     .with(ConditionalMiddleware::new(budget.wrap(Enrichment), budget.clone()))
+///     .with(budget.downstream())
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct LatencyBudget {
+    id: usize,
+    budget: Duration,
+    window: Duration,
+    min_samples: usize,
+    state: Arc<Mutex<State>>,
+}
+
+impl_condition_ops!(LatencyBudget);
+
+impl LatencyBudget {
+    /// Creates a condition disabling the middleware while the p99 of the latency it added over
+    /// the last `window` exceeds `budget`
+    pub fn new(budget: Duration, window: Duration) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            budget,
+            window,
+            min_samples: 100,
+            state: Arc::default(),
+        }
+    }
+
+    /// Sets how many calls must be measured before the p99 is acted on
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples.max(1);
+        self
+    }
+
+    fn slice_index(&self, origin: Instant, now: Instant) -> u64 {
+        let len = (self.window / SLICES as u32).as_nanos().max(1);
+        (now.duration_since(origin).as_nanos() / len) as u64
+    }
+
+    /// Wraps `middleware` so that the latency it adds is measured
+    pub fn wrap<M>(&self, middleware: M) -> LatencyTracked<M>
+    where
+        M: Middleware,
+    {
+        LatencyTracked {
+            inner: middleware,
+            budget: self.clone(),
+        }
+    }
+
+    /// Returns a middleware measuring the time spent below the wrapped middleware for this
+    /// budget, to be installed right after the conditional
+    pub fn downstream(&self) -> MeasureDownstream {
+        MeasureDownstream { id: self.id }
+    }
+
+    fn record(&self, overhead: Duration) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        if state.disabled_until.is_some() {
+            return;
+        }
+
+        let index = self.slice_index(state.origin, now);
+        let slice = &mut state.slices[(index % SLICES as u64) as usize];
+        if slice.index != index {
+            *slice = Slice {
+                index,
+                counts: [0; BINS],
+            };
+        }
+        slice.counts[bin(overhead)] += 1;
+
+        state.unchecked += 1;
+        if state.unchecked < CHECK_EVERY.min(self.min_samples) {
+            return;
+        }
+        state.unchecked = 0;
+        let slices = state.slices;
+        drop(state);
+
+        let (p99, total) = p99(&slices, index);
+        if total >= self.min_samples as u64 && p99 > self.budget {
+            let mut state = self.state.lock().unwrap();
+            if state.disabled_until.is_none() {
+                tracing::warn!(?p99, budget = ?self.budget, "middleware over latency budget");
+                state.disabled_until = Some(now + self.window);
+                for slice in &mut state.slices {
+                    slice.counts = [0; BINS];
+                }
+            }
+        }
+    }
+}
+
+impl Condition for LatencyBudget {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.disabled_until {
+            Some(until) if Instant::now() < until => false,
+            Some(_) => {
+                state.disabled_until = None;
+                true
+            }
+            None => true,
+        }
+    }
+}
+
+/// A middleware measuring the latency added by the middleware it wraps for a
+/// [`LatencyBudget`]. Created by [`LatencyBudget::wrap`].
+#[derive(Clone, Debug)]
+pub struct LatencyTracked<M> {
+    inner: M,
+    budget: LatencyBudget,
+}
+
+#[async_trait]
+impl<M> Middleware for LatencyTracked<M>
+where
+    M: Middleware,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        DownstreamTime::take(extensions, self.budget.id);
+        let start = Instant::now();
+        let result = self.inner.handle(req, extensions, next).await;
+        let elapsed = start.elapsed();
+
+        let downstream = DownstreamTime::take(extensions, self.budget.id);
+        self.budget.record(elapsed.saturating_sub(downstream));
+        result
+    }
+}
+
+/// A middleware measuring the time spent in the rest of the middleware stack for a
+/// [`LatencyBudget`]. Created by [`LatencyBudget::downstream`].
+#[derive(Clone, Copy, Debug)]
+pub struct MeasureDownstream {
+    id: usize,
+}
+
+#[async_trait]
+impl Middleware for MeasureDownstream {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let start = Instant::now();
+        let result = next.run(req, extensions).await;
+        // Retries made by the wrapped middleware each pass through here
+        DownstreamTime::add(extensions, self.id, start.elapsed());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::End;
+    use crate::ConditionalMiddleware;
+
+    /// Sleeps before passing the request along
+    struct Slow(Duration);
+
+    #[async_trait]
+    impl Middleware for Slow {
+        async fn handle(
+            &self,
+            req: Request,
+            extensions: &mut Extensions,
+            next: Next<'_>,
+        ) -> Result<Response> {
+            tokio::time::sleep(self.0).await;
+            next.run(req, extensions).await
+        }
+    }
+
+    fn histogram(overheads: impl IntoIterator<Item = Duration>) -> [Slice; SLICES] {
+        let mut slices = State::default().slices;
+        for overhead in overheads {
+            slices[0].counts[bin(overhead)] += 1;
+        }
+        slices
+    }
+
+    #[test]
+    fn test_bins() {
+        for micros in (1..100_000).step_by(7) {
+            let overhead = Duration::from_micros(micros);
+            let upper = upper_bound(bin(overhead));
+            assert!(overhead < upper, "{overhead:?} {upper:?}");
+            assert!(
+                upper.as_micros() <= 2 + micros as u128 * 5 / 4,
+                "{overhead:?}"
+            );
+        }
+        assert_eq!(BINS - 1, bin(Duration::MAX));
+    }
+
+    #[test]
+    fn test_p99() {
+        let slices = histogram((1..=200).map(Duration::from_millis));
+        let (p99, total) = p99(&slices, 0);
+        assert_eq!(200, total);
+        assert!(
+            (Duration::from_millis(198)..Duration::from_millis(248)).contains(&p99),
+            "{p99:?}"
+        );
+
+        let (p99, _) = super::p99(&histogram([Duration::from_millis(7)]), 0);
+        assert!((Duration::from_millis(7)..Duration::from_millis(9)).contains(&p99));
+
+        // Slices that left the window are not counted
+        assert_eq!((Duration::ZERO, 0), super::p99(&slices, SLICES as u64));
+    }
+
+    #[tokio::test]
+    async fn test_latency_budget() {
+        let budget = LatencyBudget::new(Duration::from_millis(10), Duration::from_millis(200))
+            .with_min_samples(3);
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(
+                    budget.wrap(Slow(Duration::from_millis(30))),
+                    budget.clone(),
+                ))
+                .with(budget.downstream())
+                // Time spent downstream is not attributed to the wrapped middleware
+                .with(Slow(Duration::from_millis(50)))
+                .with(End)
+                .build();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let extensions = Extensions::new();
+
+        for _ in 0..3 {
+            assert!(budget.evaluate(&req, &extensions));
+            client.get("http://localhost").send().await.unwrap();
+        }
+        assert!(!budget.evaluate(&req, &extensions));
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(budget.evaluate(&req, &extensions));
+    }
+
+    #[tokio::test]
+    async fn test_stacked_budgets() {
+        let outer = LatencyBudget::new(Duration::from_millis(10), Duration::from_secs(60))
+            .with_min_samples(3);
+        let inner = LatencyBudget::new(Duration::from_millis(10), Duration::from_secs(60));
+        let skip = |_req: &Request| false;
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(
+                    outer.wrap(Slow(Duration::from_millis(30))),
+                    outer.clone(),
+                ))
+                .with(outer.downstream())
+                .with(ConditionalMiddleware::new(
+                    inner.wrap(Slow(Duration::ZERO)),
+                    skip,
+                ))
+                .with(inner.downstream())
+                .with(Slow(Duration::from_millis(50)))
+                .with(End)
+                .build();
+
+        // The inner budget's measurement does not count towards the outer one
+        for _ in 0..3 {
+            client.get("http://localhost").send().await.unwrap();
+        }
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        assert!(!outer.evaluate(&req, &Extensions::new()));
+    }
+
+    #[tokio::test]
+    async fn test_within_budget() {
+        let budget = LatencyBudget::new(Duration::from_millis(40), Duration::from_secs(60))
+            .with_min_samples(2);
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(
+                    budget.wrap(Slow(Duration::ZERO)),
+                    budget.clone(),
+                ))
+                .with(budget.downstream())
+                .with(Slow(Duration::from_millis(50)))
+                .with(End)
+                .build();
+
+        for _ in 0..3 {
+            client.get("http://localhost").send().await.unwrap();
+        }
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        assert!(budget.evaluate(&req, &Extensions::new()));
+    }
+}
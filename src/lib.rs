@@ -1,10 +1,13 @@
-//! The only export of this crate is a struct [`ConditionalMiddleware`] for creating conditional middlewares.
+//! The main export of this crate is a struct [`ConditionalMiddleware`] for creating conditional middlewares.
 //! This struct implements the [`Middleware`][reqwest_middleware::Middleware] trait
 //! and forwards requests on to the middleware that it wraps.
 //!
-//! The conditional wrapper holds a closure that will be run for each request. If the
-//! closure returns true, then the inner middleware will run. Otherwise it will be
-//! skipped and the current request will be passed along to the next middleware.
+//! The conditional wrapper holds a [`Condition`] that will be evaluated for each request. If
+//! the condition returns true, then the inner middleware will run. Otherwise it will be
+//! skipped and the current request will be passed along to the next middleware. Any
+//! `Fn(&Request) -> bool` closure can be used as a condition, and
+//! [`condition::from_fn`] builds one from a closure that also receives the request's
+//! [`Extensions`][http::Extensions].
 //!
 //! # Example
 //!
@@ -42,32 +45,601 @@
 use async_trait::async_trait;
 use http::Extensions;
 use reqwest::{Request, Response};
-use reqwest_middleware::{Middleware, Next, Result};
+use reqwest_middleware::{Error, Middleware, Next, Result};
+use std::any::type_name;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::downstream::handle_marked;
+
+mod boxed;
+mod breaker;
+mod builder;
+mod canary;
+pub mod condition;
+mod control;
+mod downstream;
+mod env;
+mod error_rate;
+mod every;
+mod flag;
+mod in_flight;
+mod latency;
+pub mod matchers;
+mod memoize;
+mod online;
+#[cfg(feature = "openfeature")]
+mod openfeature;
+mod rate_limit;
+mod rollout;
+mod schedule;
+mod semaphore;
+mod swap;
+mod switch;
+mod toggle;
+mod warm_up;
+mod watch;
+
+pub use boxed::BoxedConditionalMiddleware;
+pub use breaker::{Breaker, CircuitBreaker};
+pub use builder::ConditionalMiddlewareBuilder;
+pub use canary::Canary;
+pub use condition::{AsyncCondition, Condition, Decision, TryCondition};
+pub use control::{ControlHandle, KillSwitchRegistry, Override};
+pub use downstream::MarkDownstream;
+pub use env::EnvVar;
+pub use error_rate::{ErrorRate, ErrorRateTracked};
+pub use every::EveryNth;
+pub use flag::{FlagCondition, FlagProvider};
+pub use in_flight::{InFlight, InFlightTracked};
+pub use latency::{LatencyBudget, LatencyTracked, MeasureDownstream};
+pub use memoize::Memoize;
+pub use online::{ConnectivityProbe, Online};
+#[cfg(feature = "openfeature")]
+pub use openfeature::OpenFeatureFlags;
+pub use rate_limit::RateLimit;
+pub use rollout::Rollout;
+pub use schedule::{Schedule, Weekday};
+pub use semaphore::{Gated, SemaphoreGate};
+pub use swap::{ConditionHandle, MiddlewareHandle, SwappableCondition, SwappableMiddleware};
+pub use switch::ConditionalSwitch;
+pub use toggle::{Toggle, ToggleHandle};
+pub use warm_up::WarmUp;
+pub use watch::Watch;
+
+/// What a [`ConditionalMiddleware`] should do when its condition fails to evaluate
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnConditionError {
+    /// Run the inner middleware (fail open)
+    Run,
+    /// Skip the inner middleware, as if the condition had returned false (fail closed)
+    Skip,
+    /// Abort the request and return the condition's error
+    #[default]
+    Fail,
+}
+
+/// A marker that, when present in a request's extensions, makes every [`ConditionalMiddleware`]
+/// run its inner middleware for that request without evaluating its condition
+///
+/// ```
+/// # async fn run(client: reqwest_middleware::ClientWithMiddleware) {
+/// use reqwest_conditional_middleware::ForceRun;
+///
+/// let resp = client
+///     .get("https://api.example.com")
+///     .with_extension(ForceRun)
+///     .send()
+///     .await;
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ForceRun;
+
+/// A marker that, when present in a request's extensions, makes every [`ConditionalMiddleware`]
+/// skip its inner middleware for that request without evaluating its condition. This takes
+/// precedence over [`ForceRun`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ForceSkip;
+
+/// A [`Middleware`][reqwest_middleware::Middleware] that passes every request straight along
+/// to the next middleware. This is what a [`ConditionalMiddleware`] runs when its condition
+/// is false and no alternate middleware has been given.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Passthrough;
+
+#[async_trait]
+impl Middleware for Passthrough {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        next.run(req, extensions).await
+    }
+}
+
+/// The error returned when a condition does not finish evaluating within the timeout set with
+/// [`ConditionalMiddleware::with_timeout`]
+#[derive(Debug)]
+pub struct ConditionTimedOut {
+    timeout: Duration,
+}
+
+impl ConditionTimedOut {
+    /// Returns the timeout that was exceeded
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+impl fmt::Display for ConditionTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "condition did not evaluate within {:?}", self.timeout)
+    }
+}
+
+impl std::error::Error for ConditionTimedOut {}
+
+#[derive(Clone, Copy, Debug)]
+struct Timeout {
+    duration: Duration,
+    fallback: OnConditionError,
+}
+
+/// A condition on the response, and the middleware to send the request through again when it
+/// matches. The inner middleware is used when no middleware is set.
+#[derive(Clone)]
+struct Rerun {
+    condition: Arc<dyn Fn(&Response) -> bool + Send + Sync>,
+    middleware: Option<Arc<dyn Middleware>>,
+}
+
+type RequestHook = Arc<dyn Fn(&mut Request, &mut Extensions) + Send + Sync>;
+
+/// A [`Middleware`][reqwest_middleware::Middleware] that may or may not be present. When it
+/// is absent requests are passed straight along to the next middleware. Created by
+/// [`ConditionalMiddleware::from_option`].
+#[derive(Clone, Debug)]
+pub struct Optional<M>(Option<M>);
+
+#[async_trait]
+impl<M> Middleware for Optional<M>
+where
+    M: Middleware,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        match &self.0 {
+            Some(middleware) => middleware.handle(req, extensions, next).await,
+            None => next.run(req, extensions).await,
+        }
+    }
+}
+
+/// The settings of a [`ConditionalMiddleware`] that do not depend on its type parameters
+#[derive(Clone, Default)]
+struct Options {
+    hook: Option<RequestHook>,
+    name: Option<String>,
+    label: Option<String>,
+    on_error: OnConditionError,
+    timeout: Option<Timeout>,
+    bypass_inner_on_error: bool,
+    rerun: Option<Rerun>,
+    control: Option<ControlHandle>,
+}
 
 /// A struct for holding a [`Middleware`][reqwest_middleware::Middleware] T that will be
-/// run when C evaluates to true
-pub struct ConditionalMiddleware<T, C> {
+/// run when C evaluates to true, and a middleware E that will be run otherwise
+#[derive(Clone)]
+pub struct ConditionalMiddleware<T, C, E = Passthrough> {
     inner: T,
+    otherwise: E,
     condition: C,
+    options: Options,
 }
 
 impl<T, C> ConditionalMiddleware<T, C>
 where
     T: Middleware,
-    C: Fn(&Request) -> bool + Send + Sync + 'static,
+    C: AsyncCondition,
 {
-    /// Creates a new wrapped middleware. The function C will be run for each request to
+    /// Creates a new wrapped middleware. The condition C will be evaluated for each request to
     /// determine if the wrapped middleware should be run.
     pub fn new(inner: T, condition: C) -> Self {
-        Self { inner, condition }
+        Self::with_else(inner, Passthrough, condition)
+    }
+
+    /// Creates a new named wrapped middleware. The name is included in log events and errors
+    /// emitted by the conditional, so that it can be told apart from other conditionals.
+    pub fn named(name: impl Into<String>, inner: T, condition: C) -> Self {
+        Self::new(inner, condition).with_name(name)
+    }
+}
+
+impl<M> ConditionalMiddleware<Optional<M>, condition::Constant>
+where
+    M: Middleware,
+{
+    /// Creates a conditional that always runs `inner` when it is `Some`, and always passes
+    /// requests along to the next middleware when it is `None`. This makes installing a
+    /// middleware depending on configuration possible without changing the client's type.
+    ///
+    /// ```
+    /// use reqwest_conditional_middleware::ConditionalMiddleware;
+    /// # use reqwest_conditional_middleware::Passthrough as Tracing;
+    ///
+    /// # let tracing_enabled = true;
+    /// let conditional = ConditionalMiddleware::from_option(tracing_enabled.then_some(Tracing));
+    /// ```
+    pub fn from_option(inner: Option<M>) -> Self {
+        let enabled = inner.is_some();
+        Self::new(Optional(inner), condition::constant(enabled))
+    }
+}
+
+impl<T, C, E> ConditionalMiddleware<T, C, E>
+where
+    T: Middleware,
+    C: AsyncCondition,
+    E: Middleware,
+{
+    /// Creates a new wrapped middleware that runs `inner` when the condition C evaluates to
+    /// true and `otherwise` when it evaluates to false
+    pub fn with_else(inner: T, otherwise: E, condition: C) -> Self {
+        Self {
+            inner,
+            otherwise,
+            condition,
+            options: Options::default(),
+        }
+    }
+
+    /// Sets the name of the conditional. The name is included in log events and errors
+    /// emitted by the conditional, so that it can be told apart from other conditionals.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.options.name = Some(name.into());
+        self
+    }
+
+    /// Sets a label describing the condition, shown in place of the condition's type name
+    /// when the conditional is formatted with `Debug`
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.options.label = Some(label.into());
+        self
+    }
+
+    /// Sets a hook that is run for each request before the condition is evaluated. The hook may
+    /// inspect and modify the request and its extensions, and the modified request is what the
+    /// condition sees and what is passed along to the next middleware. This covers deciding
+    /// based on a control header that should not be sent:
+    ///
+    /// ```
+    /// use http::Extensions;
+    /// use reqwest::Request;
+    /// use reqwest_conditional_middleware::{condition, ConditionalMiddleware};
+    /// # use reqwest_conditional_middleware::Passthrough as Inner;
+    ///
+    /// #[derive(Clone)]
+    /// struct SkipInner;
+    ///
+    /// let conditional = ConditionalMiddleware::new(
+    ///     Inner,
+    ///     condition::from_fn(|_req: &Request, extensions: &Extensions| {
+    ///         extensions.get::<SkipInner>().is_none()
+    ///     }),
+    /// )
+    /// .before_evaluate(|req: &mut Request, extensions: &mut Extensions| {
+    ///     if req.headers_mut().remove("x-skip-inner").is_some() {
+    ///         extensions.insert(SkipInner);
+    ///     }
+    /// });
+    /// ```
+    pub fn before_evaluate<H>(mut self, hook: H) -> Self
+    where
+        H: Fn(&mut Request, &mut Extensions) + Send + Sync + 'static,
+    {
+        self.options.hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets the policy applied when the condition returns an error. Defaults to
+    /// [`OnConditionError::Fail`].
+    pub fn on_error(mut self, policy: OnConditionError) -> Self {
+        self.options.on_error = policy;
+        self
+    }
+
+    /// Makes the inner middleware best-effort: when it returns a middleware error, the request
+    /// is sent again directly to the next middleware, bypassing the inner middleware, with the
+    /// extensions as they were before it ran. This is meant for optional middleware, such as
+    /// telemetry, that must never break real traffic.
+    ///
+    /// Install [`MarkDownstream`] right after the conditional so that requests are not sent
+    /// twice: errors are then only bypassed if the inner middleware failed before passing the
+    /// request on. Without it, an error from further down the stack can not be told apart from
+    /// one of the inner middleware, and a request that was already sent may be sent again.
+    /// Requests whose body can not be cloned (streaming bodies) are not retried.
+    pub fn bypass_inner_on_error(mut self) -> Self {
+        self.options.bypass_inner_on_error = true;
+        self
+    }
+
+    /// Sets a condition evaluated on the response. When it returns true the request is sent
+    /// again, this time through the inner middleware, and the second response is returned. For
+    /// instance a token-refresh middleware can be run when a response is a 401.
+    ///
+    /// The condition is evaluated once, whether or not the inner middleware ran for the first
+    /// attempt. Requests whose body can not be cloned (streaming bodies) are not sent again.
+    ///
+    /// ```
+    /// use reqwest::{Request, Response};
+    /// use reqwest_conditional_middleware::ConditionalMiddleware;
+    /// # use reqwest_conditional_middleware::Passthrough as RefreshToken;
+    ///
+    /// let conditional = ConditionalMiddleware::new(RefreshToken, |_req: &Request| false)
+    ///     .rerun_when(|resp: &Response| resp.status() == http::StatusCode::UNAUTHORIZED);
+    /// ```
+    pub fn rerun_when<P>(mut self, condition: P) -> Self
+    where
+        P: Fn(&Response) -> bool + Send + Sync + 'static,
+    {
+        self.options.rerun = Some(Rerun {
+            condition: Arc::new(condition),
+            middleware: None,
+        });
+        self
+    }
+
+    /// Like [`rerun_when`][Self::rerun_when], but sends the request again through `middleware`
+    /// instead of the inner middleware
+    pub fn rerun_with<P, M>(mut self, condition: P, middleware: M) -> Self
+    where
+        P: Fn(&Response) -> bool + Send + Sync + 'static,
+        M: Middleware,
+    {
+        self.options.rerun = Some(Rerun {
+            condition: Arc::new(condition),
+            middleware: Some(Arc::new(middleware)),
+        });
+        self
+    }
+
+    /// Bounds the time spent evaluating an [`AsyncCondition`]. When the condition does not
+    /// finish within `duration`, `fallback` decides what happens to the request; with
+    /// [`OnConditionError::Fail`] a [`ConditionTimedOut`] error is returned.
+    pub fn with_timeout(mut self, duration: Duration, fallback: OnConditionError) -> Self {
+        self.options.timeout = Some(Timeout { duration, fallback });
+        self
+    }
+
+    /// Installs a [`ControlHandle`] through which the condition can be overridden at runtime,
+    /// forcing the inner middleware to run for every request or disabling it
+    pub fn with_control(mut self, handle: ControlHandle) -> Self {
+        self.options.control = Some(handle);
+        self
+    }
+
+    /// Returns the name of the conditional, if it has one
+    pub fn name(&self) -> Option<&str> {
+        self.options.name.as_deref()
+    }
+
+    /// Returns a reference to the middleware that runs when the condition evaluates to true
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a reference to the middleware that runs when the condition evaluates to false
+    pub fn otherwise(&self) -> &E {
+        &self.otherwise
+    }
+
+    /// Returns a reference to the condition
+    pub fn condition(&self) -> &C {
+        &self.condition
+    }
+
+    /// Consumes the conditional, returning the middleware that runs when the condition
+    /// evaluates to true
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Returns a conditional wrapping the same middleware with its condition replaced by
+    /// `condition`. Any label set on the previous condition is discarded.
+    pub fn with_condition<D>(self, condition: D) -> ConditionalMiddleware<T, D, E>
+    where
+        D: AsyncCondition,
+    {
+        self.map_condition(|_| condition)
+    }
+
+    /// Returns a conditional wrapping the same middleware with its condition transformed by
+    /// `f`, for instance to tighten it with [`Condition::and`]. Any label set on the previous
+    /// condition is discarded.
+    pub fn map_condition<D, F>(self, f: F) -> ConditionalMiddleware<T, D, E>
+    where
+        D: AsyncCondition,
+        F: FnOnce(C) -> D,
+    {
+        ConditionalMiddleware {
+            inner: self.inner,
+            otherwise: self.otherwise,
+            condition: f(self.condition),
+            options: Options {
+                label: None,
+                ..self.options
+            },
+        }
+    }
+
+    /// Consumes the conditional, returning the middleware that runs when the condition
+    /// evaluates to true, the condition, and the middleware that runs when it evaluates to
+    /// false
+    pub fn into_parts(self) -> (T, C, E) {
+        (self.inner, self.condition, self.otherwise)
+    }
+}
+
+impl<T, C, E> ConditionalMiddleware<T, C, E>
+where
+    T: Middleware,
+    C: Condition,
+    E: Middleware,
+{
+    /// Returns a conditional wrapping the same middleware that runs when the condition C
+    /// evaluates to false instead of true
+    pub fn invert(self) -> ConditionalMiddleware<T, condition::Not<C>, E> {
+        let label = self
+            .options
+            .label
+            .as_ref()
+            .map(|label| format!("not({label})"));
+        let mut inverted = self.map_condition(Condition::not);
+        inverted.options.label = label;
+        inverted
+    }
+}
+
+impl<T, C, E> fmt::Debug for ConditionalMiddleware<T, C, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConditionalMiddleware")
+            .field("name", &self.options.name)
+            .field("inner", &type_name::<T>())
+            .field("otherwise", &type_name::<E>())
+            .field(
+                "condition",
+                &self.options.label.as_deref().unwrap_or(type_name::<C>()),
+            )
+            .field("on_error", &self.options.on_error)
+            .field(
+                "timeout",
+                &self.options.timeout.map(|timeout| timeout.duration),
+            )
+            .finish()
+    }
+}
+
+impl<T, C, E> ConditionalMiddleware<T, C, E>
+where
+    C: AsyncCondition,
+{
+    /// Evaluates the condition, applying per-request overrides and the timeout and error
+    /// policies
+    async fn decide(&self, req: &Request, extensions: &Extensions) -> Result<Decision> {
+        match self.options.control.as_ref().map(ControlHandle::get) {
+            Some(Override::ForceRun) => return Ok(Decision::Run),
+            Some(Override::ForceSkip) => return Ok(Decision::Skip),
+            Some(Override::UsePredicate) | None => {}
+        }
+        if extensions.get::<ForceSkip>().is_some() {
+            return Ok(Decision::Skip);
+        }
+        if extensions.get::<ForceRun>().is_some() {
+            return Ok(Decision::Run);
+        }
+
+        let evaluation = self.condition.evaluate_async(req, extensions);
+        let result = match self.options.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout.duration, evaluation).await {
+                Ok(result) => result,
+                Err(_) => {
+                    tracing::warn!(
+                        conditional = self.options.name.as_deref(),
+                        timeout = ?timeout.duration,
+                        policy = ?timeout.fallback,
+                        "condition timed out"
+                    );
+                    let err = ConditionTimedOut {
+                        timeout: timeout.duration,
+                    };
+                    return self.fallback(timeout.fallback, err);
+                }
+            },
+            None => evaluation.await,
+        };
+
+        result.or_else(|err| {
+            tracing::warn!(
+                conditional = self.options.name.as_deref(),
+                error = %err,
+                policy = ?self.options.on_error,
+                "condition failed to evaluate"
+            );
+            self.fallback(self.options.on_error, err)
+        })
+    }
+
+    fn fallback<Err>(&self, policy: OnConditionError, err: Err) -> Result<Decision>
+    where
+        Err: std::error::Error + Send + Sync + 'static,
+    {
+        match policy {
+            OnConditionError::Run => Ok(Decision::Run),
+            OnConditionError::Skip => Ok(Decision::Skip),
+            OnConditionError::Fail => Err(match &self.options.name {
+                Some(name) => Error::Middleware(
+                    anyhow::Error::new(err)
+                        .context(format!("condition of `{name}` failed to evaluate")),
+                ),
+                None => Error::middleware(err),
+            }),
+        }
+    }
+}
+
+impl<T, C, E> ConditionalMiddleware<T, C, E>
+where
+    T: Middleware,
+{
+    /// Runs the inner middleware, bypassing it if it fails and that has been enabled
+    async fn run_inner(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let retry = if self.options.bypass_inner_on_error {
+            req.try_clone()
+        } else {
+            None
+        };
+        let Some(retry) = retry else {
+            return self.inner.handle(req, extensions, next).await;
+        };
+
+        // The inner middleware may change the extensions before failing
+        let snapshot = extensions.clone();
+        match handle_marked(&self.inner, req, extensions, next.clone()).await {
+            // Once the request went further down the stack it may have been sent already
+            (Err(err), None) if err.is_middleware() => {
+                tracing::warn!(
+                    conditional = self.options.name.as_deref(),
+                    error = %err,
+                    "inner middleware failed, bypassing it"
+                );
+                *extensions = snapshot;
+                next.run(retry, extensions).await
+            }
+            (result, _) => result,
+        }
     }
 }
 
 #[async_trait]
-impl<T, C> Middleware for ConditionalMiddleware<T, C>
+impl<T, C, E> Middleware for ConditionalMiddleware<T, C, E>
 where
     T: Middleware,
-    C: Fn(&Request) -> bool + Send + Sync + 'static,
+    C: AsyncCondition,
+    E: Middleware,
 {
     async fn handle(
         &self,
@@ -75,12 +647,48 @@ where
         extensions: &mut Extensions,
         next: Next<'_>,
     ) -> Result<Response> {
-        let should_handle = (self.condition)(&req);
+        let mut req = req;
+        if let Some(hook) = &self.options.hook {
+            hook(&mut req, extensions);
+        }
 
-        if should_handle {
-            self.inner.handle(req, extensions, next).await
-        } else {
-            next.run(req, extensions).await
+        let decision = self.decide(&req, extensions).await?;
+
+        tracing::trace!(
+            conditional = self.options.name.as_deref(),
+            decision = decision.as_str(),
+            "evaluated condition"
+        );
+
+        let rerun = self
+            .options
+            .rerun
+            .as_ref()
+            .and_then(|rerun| Some((rerun, req.try_clone()?)));
+
+        let result = match decision {
+            Decision::Run => self.run_inner(req, extensions, next.clone()).await,
+            Decision::Skip => self.otherwise.handle(req, extensions, next.clone()).await,
+            Decision::ShortCircuit(response) => return Ok(response),
+        };
+
+        let Some((rerun, retry)) = rerun else {
+            return result;
+        };
+        let response = result?;
+        if !(rerun.condition)(&response) {
+            return Ok(response);
+        }
+
+        tracing::trace!(
+            conditional = self.options.name.as_deref(),
+            status = %response.status(),
+            "response matched, running request again"
+        );
+
+        match &rerun.middleware {
+            Some(middleware) => middleware.handle(retry, extensions, next).await,
+            None => self.run_inner(retry, extensions, next).await,
         }
     }
 }
@@ -92,7 +700,7 @@ mod tests {
     use reqwest::{Request, Response};
     use std::sync::{Arc, Mutex};
 
-    struct End;
+    pub(crate) struct End;
 
     #[async_trait]
     impl Middleware for End {
@@ -108,12 +716,12 @@ mod tests {
         }
     }
 
-    struct CheckMiddleware {
+    pub(crate) struct CheckMiddleware {
         check: Arc<Mutex<bool>>,
     }
 
     impl CheckMiddleware {
-        fn new() -> Self {
+        pub(crate) fn new() -> Self {
             Self {
                 check: Arc::new(Mutex::new(false)),
             }
@@ -124,7 +732,7 @@ mod tests {
             *self.check.lock().unwrap() = !value;
         }
 
-        fn checker(&self) -> Arc<Mutex<bool>> {
+        pub(crate) fn checker(&self) -> Arc<Mutex<bool>> {
             self.check.clone()
         }
     }
@@ -179,4 +787,627 @@ mod tests {
         assert_eq!("end", resp);
         assert!(!*test.lock().unwrap())
     }
+
+    struct IsPost;
+
+    impl Condition for IsPost {
+        fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+            req.method() == http::Method::POST
+        }
+    }
+
+    #[tokio::test]
+    async fn test_named_condition() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let conditional = ConditionalMiddleware::new(check, IsPost);
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(End)
+                .build();
+
+        let request = reqwest::Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        client.execute(request).await.unwrap();
+        assert!(!*test.lock().unwrap());
+
+        let request =
+            reqwest::Request::new(http::Method::POST, "http://localhost".parse().unwrap());
+        client.execute(request).await.unwrap();
+        assert!(*test.lock().unwrap())
+    }
+
+    #[derive(Clone)]
+    struct Enabled;
+
+    #[tokio::test]
+    async fn test_condition_sees_extensions() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let conditional = ConditionalMiddleware::new(
+            check,
+            condition::from_fn(|_req: &Request, extensions: &Extensions| {
+                extensions.get::<Enabled>().is_some()
+            }),
+        );
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(End)
+                .build();
+
+        client.get("http://localhost").send().await.unwrap();
+        assert!(!*test.lock().unwrap());
+
+        client
+            .get("http://localhost")
+            .with_extension(Enabled)
+            .send()
+            .await
+            .unwrap();
+        assert!(*test.lock().unwrap())
+    }
+
+    fn failing_conditional(
+        check: CheckMiddleware,
+        policy: OnConditionError,
+    ) -> ConditionalMiddleware<CheckMiddleware, impl TryCondition> {
+        ConditionalMiddleware::new(
+            check,
+            condition::fallible(|_req: &Request, _extensions: &Extensions| {
+                Err::<bool, _>(std::fmt::Error)
+            }),
+        )
+        .on_error(policy)
+    }
+
+    #[tokio::test]
+    async fn test_condition_error_policies() {
+        for (policy, ran) in [
+            (OnConditionError::Run, true),
+            (OnConditionError::Skip, false),
+        ] {
+            let check = CheckMiddleware::new();
+            let test = check.checker();
+
+            let client =
+                reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                    .with(failing_conditional(check, policy))
+                    .with(End)
+                    .build();
+
+            let resp = client.get("http://localhost").send().await.unwrap();
+
+            assert_eq!("end", resp.text().await.unwrap());
+            assert_eq!(ran, *test.lock().unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_condition_error_fails_request() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(failing_conditional(check, OnConditionError::Fail))
+                .with(End)
+                .build();
+
+        let err = client.get("http://localhost").send().await.unwrap_err();
+
+        assert!(err.is_middleware());
+        assert!(!*test.lock().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_runs_else_middleware() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let other = CheckMiddleware::new();
+        let other_test = other.checker();
+        let conditional = ConditionalMiddleware::with_else(check, other, |req: &Request| {
+            req.method() == http::Method::GET
+        });
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(End)
+                .build();
+
+        let resp = client.post("http://localhost").send().await.unwrap();
+
+        assert_eq!("end", resp.text().await.unwrap());
+        assert!(!*test.lock().unwrap());
+        assert!(*other_test.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_short_circuit() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let conditional = ConditionalMiddleware::new(
+            check,
+            condition::decide(|_req: &Request, _extensions: &Extensions| {
+                let builder = http::Response::builder().status(StatusCode::FORBIDDEN);
+                Decision::ShortCircuit(builder.body("short").unwrap().into())
+            }),
+        );
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(End)
+                .build();
+
+        let resp = client.get("http://localhost").send().await.unwrap();
+
+        assert_eq!(StatusCode::FORBIDDEN, resp.status());
+        assert_eq!("short", resp.text().await.unwrap());
+        assert!(!*test.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_invert() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let conditional = ConditionalMiddleware::new(check, |_req: &Request| false).invert();
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(End)
+                .build();
+
+        client.get("http://localhost").send().await.unwrap();
+
+        assert!(*test.lock().unwrap());
+    }
+
+    #[test]
+    fn test_accessors() {
+        let conditional = ConditionalMiddleware::new(CheckMiddleware::new(), IsPost);
+        let request =
+            reqwest::Request::new(http::Method::POST, "http://localhost".parse().unwrap());
+
+        assert!(!*conditional.inner().checker().lock().unwrap());
+        assert!(conditional
+            .condition()
+            .evaluate(&request, &Extensions::new()));
+
+        let check = conditional.into_inner();
+        check.flip();
+        assert!(*check.checker().lock().unwrap());
+    }
+
+    #[derive(Clone)]
+    struct CloneableCheck(Arc<Mutex<u32>>);
+
+    #[async_trait]
+    impl Middleware for CloneableCheck {
+        async fn handle(
+            &self,
+            req: Request,
+            extensions: &mut Extensions,
+            next: Next<'_>,
+        ) -> Result<Response> {
+            *self.0.lock().unwrap() += 1;
+            next.run(req, extensions).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clone() {
+        let count = Arc::new(Mutex::new(0));
+        let conditional = ConditionalMiddleware::new(
+            CloneableCheck(count.clone()),
+            condition::from_fn(|_req: &Request, _extensions: &Extensions| true),
+        );
+
+        for conditional in [conditional.clone(), conditional] {
+            let client =
+                reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                    .with(conditional)
+                    .with(End)
+                    .build();
+
+            client.get("http://localhost").send().await.unwrap();
+        }
+
+        assert_eq!(2, *count.lock().unwrap());
+    }
+
+    #[test]
+    fn test_debug() {
+        let conditional = ConditionalMiddleware::new(End, IsPost);
+        let debug = format!("{conditional:?}");
+        assert!(debug.contains("tests::End"));
+        assert!(debug.contains("tests::IsPost"));
+        assert!(debug.contains("Passthrough"));
+
+        let debug = format!("{:?}", conditional.with_label("is post").invert());
+        assert!(debug.contains("condition: \"not(is post)\""));
+    }
+
+    #[tokio::test]
+    async fn test_named_error() {
+        let conditional = ConditionalMiddleware::named(
+            "gzip-gate",
+            End,
+            condition::fallible(|_req: &Request, _extensions: &Extensions| {
+                Err::<bool, _>(std::fmt::Error)
+            }),
+        );
+        assert_eq!(Some("gzip-gate"), conditional.name());
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(End)
+                .build();
+
+        let err = client.get("http://localhost").send().await.unwrap_err();
+
+        assert!(err.to_string().contains("gzip-gate"));
+    }
+
+    #[tokio::test]
+    async fn test_map_condition() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let conditional = ConditionalMiddleware::new(check, |_req: &Request| true)
+            .map_condition(|condition| condition.and(IsPost));
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(End)
+                .build();
+
+        client.get("http://localhost").send().await.unwrap();
+        assert!(!*test.lock().unwrap());
+
+        client.post("http://localhost").send().await.unwrap();
+        assert!(*test.lock().unwrap());
+    }
+
+    #[test]
+    fn test_with_condition() {
+        let conditional = ConditionalMiddleware::new(End, IsPost)
+            .with_label("is post")
+            .with_condition(|_req: &Request| false);
+        let request =
+            reqwest::Request::new(http::Method::POST, "http://localhost".parse().unwrap());
+
+        assert!(!conditional
+            .condition()
+            .evaluate(&request, &Extensions::new()));
+        assert!(!format!("{conditional:?}").contains("is post"));
+    }
+
+    struct EchoHeader(&'static str);
+
+    #[async_trait]
+    impl Middleware for EchoHeader {
+        async fn handle(
+            &self,
+            req: Request,
+            _extensions: &mut Extensions,
+            _next: Next<'_>,
+        ) -> Result<Response> {
+            let present = req.headers().contains_key(self.0);
+            let builder = http::Response::builder().status(StatusCode::OK);
+            Ok(builder.body(present.to_string()).unwrap().into())
+        }
+    }
+
+    #[derive(Clone)]
+    struct Control;
+
+    #[tokio::test]
+    async fn test_before_evaluate_hook() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let conditional = ConditionalMiddleware::new(
+            check,
+            condition::from_fn(|_req: &Request, extensions: &Extensions| {
+                extensions.get::<Control>().is_some()
+            }),
+        )
+        .before_evaluate(|req: &mut Request, extensions: &mut Extensions| {
+            if req.headers_mut().remove("x-control").is_some() {
+                extensions.insert(Control);
+            }
+        });
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(EchoHeader("x-control"))
+                .build();
+
+        let resp = client
+            .get("http://localhost")
+            .header("x-control", "1")
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!("false", resp.text().await.unwrap());
+        assert!(*test.lock().unwrap());
+    }
+
+    struct Slow;
+
+    #[async_trait]
+    impl AsyncCondition for Slow {
+        type Error = std::convert::Infallible;
+
+        async fn evaluate_async(
+            &self,
+            _req: &Request,
+            _extensions: &Extensions,
+        ) -> std::result::Result<Decision, Self::Error> {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(Decision::Run)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_condition_timeout() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let conditional = ConditionalMiddleware::new(check, Slow)
+            .with_timeout(Duration::from_millis(10), OnConditionError::Skip);
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(End)
+                .build();
+
+        let resp = client.get("http://localhost").send().await.unwrap();
+
+        assert_eq!("end", resp.text().await.unwrap());
+        assert!(!*test.lock().unwrap());
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(
+                    ConditionalMiddleware::new(End, Slow)
+                        .with_timeout(Duration::from_millis(10), OnConditionError::Fail),
+                )
+                .build();
+
+        let err = client.get("http://localhost").send().await.unwrap_err();
+
+        assert!(err.to_string().contains("did not evaluate"));
+    }
+
+    #[tokio::test]
+    async fn test_override_markers() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(check, |_req: &Request| false))
+                .with(End)
+                .build();
+
+        client
+            .get("http://localhost")
+            .with_extension(ForceRun)
+            .with_extension(ForceSkip)
+            .send()
+            .await
+            .unwrap();
+        assert!(!*test.lock().unwrap());
+
+        client
+            .get("http://localhost")
+            .with_extension(ForceRun)
+            .send()
+            .await
+            .unwrap();
+        assert!(*test.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_control_handle() {
+        let first = CheckMiddleware::new();
+        let first_test = first.checker();
+        let second = CheckMiddleware::new();
+        let second_test = second.checker();
+        let control = ControlHandle::new();
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(
+                    ConditionalMiddleware::new(first, |_req: &Request| false)
+                        .with_control(control.clone()),
+                )
+                .with(
+                    ConditionalMiddleware::new(second, |_req: &Request| true)
+                        .with_control(control.clone()),
+                )
+                .with(End)
+                .build();
+
+        client.get("http://localhost").send().await.unwrap();
+        assert!(!*first_test.lock().unwrap());
+        assert!(*second_test.lock().unwrap());
+
+        // Each run of a check middleware flips its flag
+        control.force_run();
+        client.get("http://localhost").send().await.unwrap();
+        assert!(*first_test.lock().unwrap());
+        assert!(!*second_test.lock().unwrap());
+
+        control.disable();
+        client
+            .get("http://localhost")
+            .with_extension(ForceRun)
+            .send()
+            .await
+            .unwrap();
+        assert!(*first_test.lock().unwrap());
+        assert!(!*second_test.lock().unwrap());
+
+        control.reset();
+        client.get("http://localhost").send().await.unwrap();
+        assert!(*first_test.lock().unwrap());
+        assert!(*second_test.lock().unwrap());
+    }
+
+    struct Failing;
+
+    #[async_trait]
+    impl Middleware for Failing {
+        async fn handle(
+            &self,
+            _req: Request,
+            _extensions: &mut Extensions,
+            _next: Next<'_>,
+        ) -> Result<Response> {
+            Err(Error::middleware(std::fmt::Error))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bypass_downstream_error() {
+        let calls = Arc::new(Mutex::new(0));
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(
+                    ConditionalMiddleware::new(CheckMiddleware::new(), |_req: &Request| true)
+                        .bypass_inner_on_error(),
+                )
+                .with(MarkDownstream)
+                .with(CloneableCheck(calls.clone()))
+                .with(Failing)
+                .build();
+
+        // The request went past the inner middleware, so it is not sent again
+        assert!(client.get("http://localhost").send().await.is_err());
+        assert_eq!(1, *calls.lock().unwrap());
+
+        // Nor when it also went through another conditional bypassing its inner middleware
+        let calls = Arc::new(Mutex::new(0));
+        let bypass = |inner| {
+            ConditionalMiddleware::new(inner, |_req: &Request| true).bypass_inner_on_error()
+        };
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(bypass(CheckMiddleware::new()))
+                .with(bypass(CheckMiddleware::new()))
+                .with(MarkDownstream)
+                .with(CloneableCheck(calls.clone()))
+                .with(Failing)
+                .build();
+
+        assert!(client.get("http://localhost").send().await.is_err());
+        assert_eq!(1, *calls.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_bypass_inner_on_error() {
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(Failing, |_req: &Request| true))
+                .with(End)
+                .build();
+
+        assert!(client.get("http://localhost").send().await.is_err());
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(
+                    ConditionalMiddleware::new(Failing, |_req: &Request| true)
+                        .bypass_inner_on_error(),
+                )
+                .with(End)
+                .build();
+
+        let resp = client.get("http://localhost").send().await.unwrap();
+        assert_eq!("end", resp.text().await.unwrap());
+    }
+
+    struct Status(Arc<Mutex<Vec<StatusCode>>>);
+
+    #[async_trait]
+    impl Middleware for Status {
+        async fn handle(
+            &self,
+            _req: Request,
+            _extensions: &mut Extensions,
+            _next: Next<'_>,
+        ) -> Result<Response> {
+            let status = self.0.lock().unwrap().pop().unwrap_or(StatusCode::OK);
+            let builder = http::Response::builder().status(status);
+            Ok(builder.body("").unwrap().into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rerun_when() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let statuses = Arc::new(Mutex::new(vec![StatusCode::UNAUTHORIZED]));
+        let conditional = ConditionalMiddleware::new(check, |_req: &Request| false)
+            .rerun_when(|resp: &Response| resp.status() == StatusCode::UNAUTHORIZED);
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(conditional)
+                .with(Status(statuses.clone()))
+                .build();
+
+        let resp = client.get("http://localhost").send().await.unwrap();
+
+        assert_eq!(StatusCode::OK, resp.status());
+        assert!(*test.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_shared_condition() {
+        let first = CheckMiddleware::new();
+        let first_test = first.checker();
+        let second = CheckMiddleware::new();
+        let second_test = second.checker();
+        let condition = condition::shared(IsPost);
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(first, condition.clone()))
+                .with(ConditionalMiddleware::new(second, condition))
+                .with(End)
+                .build();
+
+        client.post("http://localhost").send().await.unwrap();
+
+        assert!(*first_test.lock().unwrap());
+        assert!(*second_test.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_from_option() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::from_option(Some(check)))
+                .with(ConditionalMiddleware::from_option(None::<CheckMiddleware>))
+                .with(End)
+                .build();
+
+        let resp = client.get("http://localhost").send().await.unwrap();
+
+        assert_eq!("end", resp.text().await.unwrap());
+        assert!(*test.lock().unwrap());
+    }
 }
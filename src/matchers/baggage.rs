@@ -0,0 +1,95 @@
+use http::Extensions;
+use opentelemetry::baggage::BaggageExt;
+use reqwest::Request;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] matching requests by an OpenTelemetry baggage entry. Created by
+/// [`has_baggage`] and [`baggage_eq`].
+#[derive(Clone, Debug)]
+pub struct Baggage {
+    key: String,
+    value: Option<String>,
+}
+
+impl_condition_ops!(Baggage);
+
+/// Matches requests whose OpenTelemetry baggage has an entry for `key`, with any value.
+///
+/// The baggage is read from an [`opentelemetry::Context`] in the request extensions if there is
+/// one, or else from the current context. Available with the `opentelemetry` feature.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::has_baggage("tenant");
+/// ```
+pub fn has_baggage(key: impl Into<String>) -> Baggage {
+    Baggage {
+        key: key.into(),
+        value: None,
+    }
+}
+
+/// Matches requests whose OpenTelemetry baggage maps `key` to `value`, so that upstream services
+/// can turn middleware on for a whole call chain by propagating an entry such as `debug=1`.
+///
+/// The baggage is read as with [`has_baggage`]. Available with the `opentelemetry` feature.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let capture = matchers::baggage_eq("debug", "1");
+/// ```
+pub fn baggage_eq(key: impl Into<String>, value: impl Into<String>) -> Baggage {
+    Baggage {
+        key: key.into(),
+        value: Some(value.into()),
+    }
+}
+
+impl Baggage {
+    fn matches(&self, context: &opentelemetry::Context) -> bool {
+        context.baggage().get(&self.key).is_some_and(|value| {
+            self.value
+                .as_deref()
+                .is_none_or(|expected| value.as_str() == expected)
+        })
+    }
+}
+
+impl Condition for Baggage {
+    fn evaluate(&self, _req: &Request, extensions: &Extensions) -> bool {
+        match extensions.get::<opentelemetry::Context>() {
+            Some(context) => self.matches(context),
+            None => opentelemetry::Context::map_current(|context| self.matches(context)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+    use opentelemetry::KeyValue;
+
+    #[test]
+    fn test_baggage() {
+        let req = request(http::Method::GET, "http://a/");
+        let mut extensions = Extensions::new();
+        assert!(!has_baggage("debug").evaluate(&req, &extensions));
+
+        let context = opentelemetry::Context::new().with_baggage([KeyValue::new("debug", "1")]);
+        {
+            let _guard = context.clone().attach();
+            assert!(baggage_eq("debug", "1").evaluate(&req, &extensions));
+        }
+
+        extensions.insert(context);
+        assert!(has_baggage("debug").evaluate(&req, &extensions));
+        assert!(baggage_eq("debug", "1").evaluate(&req, &extensions));
+        assert!(!baggage_eq("debug", "0").evaluate(&req, &extensions));
+        assert!(!has_baggage("tenant").evaluate(&req, &extensions));
+    }
+}
@@ -0,0 +1,172 @@
+use http::Extensions;
+use reqwest::Request;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] matching requests that have a body. Created by [`has_body`].
+#[derive(Clone, Copy, Debug)]
+pub struct HasBody;
+
+impl_condition_ops!(HasBody);
+
+/// Matches requests that have a body, whether buffered or streaming.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::has_body();
+/// ```
+pub fn has_body() -> HasBody {
+    HasBody
+}
+
+impl Condition for HasBody {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.body().is_some()
+    }
+}
+
+/// A [`Condition`] matching requests with a streaming body. Created by [`has_streaming_body`].
+#[derive(Clone, Copy, Debug)]
+pub struct StreamingBody;
+
+impl_condition_ops!(StreamingBody);
+
+/// Matches requests whose body is a stream, for which
+/// [`Body::as_bytes`](reqwest::Body::as_bytes) returns `None`. Middleware that has to read the
+/// whole body, such as hashing or signing, can be skipped for these requests instead of failing.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let can_sign = !matchers::has_streaming_body();
+/// ```
+pub fn has_streaming_body() -> StreamingBody {
+    StreamingBody
+}
+
+impl Condition for StreamingBody {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.body().is_some_and(|body| body.as_bytes().is_none())
+    }
+}
+
+/// A [`Condition`] matching requests with a buffered body. Created by [`has_buffered_body`].
+#[derive(Clone, Copy, Debug)]
+pub struct BufferedBody;
+
+impl_condition_ops!(BufferedBody);
+
+/// Matches requests with a body held in memory, whose bytes can be read through
+/// [`Body::as_bytes`](reqwest::Body::as_bytes). Requests without a body do not match.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::has_buffered_body();
+/// ```
+pub fn has_buffered_body() -> BufferedBody {
+    BufferedBody
+}
+
+impl Condition for BufferedBody {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.body().is_some_and(|body| body.as_bytes().is_some())
+    }
+}
+
+/// A [`Condition`] matching requests by the size of their body. Created by
+/// [`body_size_at_most`] and [`body_size_at_least`].
+#[derive(Clone, Copy, Debug)]
+pub struct BodySize {
+    min: usize,
+    max: usize,
+}
+
+impl_condition_ops!(BodySize);
+
+/// Matches requests whose body is known to be at most `max` bytes long. A request without a
+/// body has a length of zero, while streaming bodies have no known length and never match.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// // Only sign payloads that are already buffered and small
+/// let condition = matchers::body_size_at_most(64 * 1024);
+/// ```
+pub fn body_size_at_most(max: usize) -> BodySize {
+    BodySize { min: 0, max }
+}
+
+/// Matches requests whose body is known to be at least `min` bytes long. A request without a
+/// body has a length of zero, while streaming bodies have no known length and never match.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// // Compress large buffered payloads
+/// let condition = matchers::body_size_at_least(1024);
+/// ```
+pub fn body_size_at_least(min: usize) -> BodySize {
+    BodySize {
+        min,
+        max: usize::MAX,
+    }
+}
+
+impl Condition for BodySize {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        let len = match req.body() {
+            Some(body) => body.as_bytes().map(<[u8]>::len),
+            None => Some(0),
+        };
+        len.is_some_and(|len| (self.min..=self.max).contains(&len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    fn with_body(body: Option<&'static str>) -> Request {
+        let mut req = request(http::Method::POST, "http://a/");
+        *req.body_mut() = body.map(Into::into);
+        req
+    }
+
+    #[test]
+    fn test_body() {
+        let extensions = Extensions::new();
+        let empty = with_body(None);
+        let small = with_body(Some("hello"));
+
+        assert!(!has_body().evaluate(&empty, &extensions));
+        assert!(has_body().evaluate(&small, &extensions));
+
+        assert!(body_size_at_most(5).evaluate(&small, &extensions));
+        assert!(!body_size_at_most(4).evaluate(&small, &extensions));
+        assert!(body_size_at_most(0).evaluate(&empty, &extensions));
+        assert!(body_size_at_least(5).evaluate(&small, &extensions));
+        assert!(!body_size_at_least(6).evaluate(&small, &extensions));
+        assert!(!body_size_at_least(1).evaluate(&empty, &extensions));
+    }
+
+    #[test]
+    fn test_streaming_body() {
+        let extensions = Extensions::new();
+        let empty = with_body(None);
+        let buffered = with_body(Some("hello"));
+        let mut streaming = with_body(None);
+        *streaming.body_mut() = Some(reqwest::Body::wrap(String::from("hello")));
+
+        assert!(has_streaming_body().evaluate(&streaming, &extensions));
+        assert!(!has_streaming_body().evaluate(&buffered, &extensions));
+        assert!(!has_streaming_body().evaluate(&empty, &extensions));
+        assert!(has_buffered_body().evaluate(&buffered, &extensions));
+        assert!(!has_buffered_body().evaluate(&streaming, &extensions));
+        assert!(!has_buffered_body().evaluate(&empty, &extensions));
+        assert!(!body_size_at_least(0).evaluate(&streaming, &extensions));
+    }
+}
@@ -0,0 +1,181 @@
+use cel::{Context, ExecutionError, Program, Value};
+use http::Extensions;
+use reqwest::Request;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Decision, TryCondition};
+
+/// The error returned when a [`Cel`] expression cannot be compiled or evaluated
+#[derive(Debug)]
+pub enum CelError {
+    /// The expression is not valid CEL, with the parser's description of why
+    Parse(String),
+    /// Evaluating the expression failed, for instance because it looked up a missing header
+    Execution(ExecutionError),
+    /// The expression evaluated to something other than a boolean
+    NotBool(Value),
+}
+
+impl fmt::Display for CelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CelError::Parse(e) => write!(f, "invalid CEL expression: {e}"),
+            CelError::Execution(e) => write!(f, "CEL evaluation failed: {e}"),
+            CelError::NotBool(value) => {
+                write!(f, "CEL expression evaluated to {value:?} instead of a bool")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CelError::Execution(e) => Some(e),
+            CelError::Parse(_) | CelError::NotBool(_) => None,
+        }
+    }
+}
+
+/// A [`TryCondition`] evaluating a [Common Expression Language](https://cel.dev) expression
+/// against the request. Created by [`cel`].
+pub struct Cel {
+    program: Program,
+    // Holds the standard functions, which are costly to register for every request
+    functions: Context<'static, 'static>,
+}
+
+/// Compiles a [Common Expression Language](https://cel.dev) expression into a condition, so that
+/// a policy system speaking CEL can drive middleware gating. The expression is evaluated with
+/// these variables:
+///
+/// | Variable | Type | Value |
+/// | --- | --- | --- |
+/// | `method` | `string` | The method, such as `GET` |
+/// | `url` | `string` | The whole URL |
+/// | `scheme` | `string` | `http` or `https` |
+/// | `host` | `string` | The host, or an empty string |
+/// | `path` | `string` | The path, still percent-encoded |
+/// | `query` | `map(string, string)` | The first decoded value of each query parameter |
+/// | `headers` | `map(string, string)` | The headers with lowercase names, values of repeated headers joined with `, ` |
+///
+/// Use `"x-env" in headers` to test whether a header is present. Evaluation fails when the
+/// expression does not produce a bool or refers to an unknown variable, and a failure is
+/// handled by the [`OnConditionError`](crate::OnConditionError) policy of the middleware. As
+/// a [`TryCondition`] the result can't be combined with the `&`, `|` and `!` operators.
+///
+/// Available with the `cel` feature.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::cel(
+///     r#"method == "GET" && host.endsWith(".example.com") && headers["x-env"] == "staging""#,
+/// )
+/// .unwrap();
+/// ```
+pub fn cel(expression: &str) -> Result<Cel, CelError> {
+    let program = Program::compile(expression).map_err(|e| CelError::Parse(e.to_string()))?;
+    Ok(Cel {
+        program,
+        functions: Context::default(),
+    })
+}
+
+impl fmt::Debug for Cel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cel").finish_non_exhaustive()
+    }
+}
+
+impl Cel {
+    /// Evaluates the expression against `req`
+    fn execute(&self, req: &Request) -> Result<bool, CelError> {
+        let url = req.url();
+        let mut headers: HashMap<String, String> = HashMap::new();
+        for (name, value) in req.headers() {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            headers
+                .entry(name.as_str().to_owned())
+                .and_modify(|values| {
+                    values.push_str(", ");
+                    values.push_str(value);
+                })
+                .or_insert_with(|| value.to_owned());
+        }
+        let mut query: HashMap<String, String> = HashMap::new();
+        for (name, value) in url.query_pairs() {
+            query.entry(name.into_owned()).or_insert(value.into_owned());
+        }
+
+        let mut context = self.functions.new_inner_scope();
+        context.add_variable_from_value("method", req.method().as_str());
+        context.add_variable_from_value("url", url.as_str());
+        context.add_variable_from_value("scheme", url.scheme());
+        context.add_variable_from_value("host", url.host_str().unwrap_or_default());
+        context.add_variable_from_value("path", url.path());
+        context.add_variable_from_value("query", query);
+        context.add_variable_from_value("headers", headers);
+
+        match self.program.execute(&context) {
+            Ok(Value::Bool(run)) => Ok(run),
+            Ok(value) => Err(CelError::NotBool(value)),
+            Err(e) => Err(CelError::Execution(e)),
+        }
+    }
+}
+
+impl TryCondition for Cel {
+    type Error = CelError;
+
+    fn try_evaluate(&self, req: &Request, _extensions: &Extensions) -> Result<Decision, CelError> {
+        self.execute(req).map(Decision::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    fn evaluate(expression: &str, req: &Request) -> Result<bool, CelError> {
+        cel(expression).unwrap().execute(req)
+    }
+
+    #[test]
+    fn test_cel() {
+        let mut req = request(http::Method::GET, "https://api.example.com/v1?debug=1");
+        req.headers_mut()
+            .append("x-env", http::HeaderValue::from_static("staging"));
+        req.headers_mut()
+            .append("x-env", http::HeaderValue::from_static("eu"));
+
+        assert!(evaluate(
+            r#"method == "GET" && scheme == "https" && host.endsWith(".example.com")"#,
+            &req
+        )
+        .unwrap());
+        assert!(evaluate(r#"path.startsWith("/v1") && query["debug"] == "1""#, &req).unwrap());
+        assert!(evaluate(r#"headers["x-env"] == "staging, eu""#, &req).unwrap());
+        assert!(!evaluate(r#""authorization" in headers"#, &req).unwrap());
+
+        assert!(matches!(
+            evaluate(r#"body == "x""#, &req),
+            Err(CelError::Execution(_))
+        ));
+        assert!(matches!(
+            evaluate(r#"method"#, &req),
+            Err(CelError::NotBool(_))
+        ));
+        assert!(matches!(cel("method == (1"), Err(CelError::Parse(_))));
+        for truncated in ["method &&", "a ||", "!", "a ? b :", "[1,", "\""] {
+            assert!(
+                matches!(cel(truncated), Err(CelError::Parse(_))),
+                "{truncated}"
+            );
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use http::header::HeaderName;
+use serde::Deserialize;
+use std::fmt;
+
+use super::header::parse_media_type;
+use super::path::try_route;
+use super::{
+    content_type, domain, has_header, header_eq, host, path_prefix, port, query_param,
+    query_param_eq, Methods, Scheme,
+};
+use crate::condition::{all, any, not};
+use crate::Condition;
+
+/// A header a [`MatcherConfig`] requires, with an optional exact value
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HeaderConfig {
+    /// The header name
+    pub name: String,
+    /// The exact value the header must have, if any
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+/// A query parameter a [`MatcherConfig`] requires, with an optional value
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueryConfig {
+    /// The parameter name
+    pub name: String,
+    /// The value the parameter must have, if any
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+/// A declarative description of a matcher, deserializable with serde from any format such as
+/// JSON, YAML or TOML, so that which requests get which middleware can be changed without a
+/// code change. Every field that is set must match, `any` matches when one of its nested
+/// configurations does, and `negate` inverts the result. An empty configuration matches every
+/// request.
+///
+/// Available with the `serde` feature.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers::MatcherConfig;
+///
+/// let config: MatcherConfig = serde_json::from_str(
+///     r#"{
+///         "domain": "oxide.computer",
+///         "path_prefix": "/v1",
+///         "methods": ["GET", "HEAD"],
+///         "headers": [{ "name": "x-environment", "value": "staging" }]
+///     }"#,
+/// )
+/// .unwrap();
+/// let condition = config.compile().unwrap();
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MatcherConfig {
+    /// See [`host`](super::host)
+    pub host: Option<String>,
+    /// See [`domain`](super::domain)
+    pub domain: Option<String>,
+    /// See [`scheme`](super::scheme)
+    pub scheme: Option<Scheme>,
+    /// See [`port`](super::port)
+    pub port: Option<u16>,
+    /// See [`path_prefix`](super::path_prefix)
+    pub path_prefix: Option<String>,
+    /// See [`route`](super::route)
+    pub route: Option<String>,
+    /// Methods the request may have, any method matches when empty
+    pub methods: Vec<String>,
+    /// Headers that must all be present
+    pub headers: Vec<HeaderConfig>,
+    /// Query parameters that must all be present
+    pub query: Vec<QueryConfig>,
+    /// See [`content_type`](super::content_type)
+    pub content_type: Option<String>,
+    /// Nested configurations, of which at least one must match when not empty
+    pub any: Vec<MatcherConfig>,
+    /// Inverts the result of this configuration
+    pub negate: bool,
+}
+
+/// The error returned by [`MatcherConfig::compile`] for configurations with invalid values
+#[derive(Debug)]
+pub struct MatcherConfigError(String);
+
+impl fmt::Display for MatcherConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid matcher configuration: {}", self.0)
+    }
+}
+
+impl std::error::Error for MatcherConfigError {}
+
+impl MatcherConfig {
+    /// Compiles this configuration into a condition, validating method names, header names,
+    /// route templates and media types
+    pub fn compile(&self) -> Result<Box<dyn Condition>, MatcherConfigError> {
+        let mut conditions: Vec<Box<dyn Condition>> = Vec::new();
+
+        if let Some(pattern) = &self.host {
+            conditions.push(Box::new(host(pattern)));
+        }
+        if let Some(domain_name) = &self.domain {
+            conditions.push(Box::new(domain(domain_name)));
+        }
+        if let Some(scheme) = self.scheme {
+            conditions.push(Box::new(scheme));
+        }
+        if let Some(target) = self.port {
+            conditions.push(Box::new(port(target)));
+        }
+        if let Some(prefix) = &self.path_prefix {
+            conditions.push(Box::new(path_prefix(prefix)));
+        }
+        if let Some(template) = &self.route {
+            conditions.push(Box::new(try_route(template).map_err(MatcherConfigError)?));
+        }
+        if !self.methods.is_empty() {
+            let methods = self
+                .methods
+                .iter()
+                .map(|method| {
+                    http::Method::from_bytes(method.as_bytes())
+                        .map_err(|_| MatcherConfigError(format!("invalid method {method:?}")))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            conditions.push(Box::new(Methods::any_of(methods)));
+        }
+        for header in &self.headers {
+            let name = HeaderName::try_from(header.name.as_str()).map_err(|_| {
+                MatcherConfigError(format!("invalid header name {:?}", header.name))
+            })?;
+            conditions.push(match &header.value {
+                Some(value) => Box::new(header_eq(name, value.clone())),
+                None => Box::new(has_header(name)),
+            });
+        }
+        for param in &self.query {
+            conditions.push(match &param.value {
+                Some(value) => Box::new(query_param_eq(param.name.clone(), value.clone())),
+                None => Box::new(query_param(param.name.clone())),
+            });
+        }
+        if let Some(media_type) = &self.content_type {
+            if parse_media_type(media_type).is_none() {
+                return Err(MatcherConfigError(format!(
+                    "invalid media type {media_type:?}"
+                )));
+            }
+            conditions.push(Box::new(content_type(media_type)));
+        }
+        if !self.any.is_empty() {
+            let nested = self
+                .any
+                .iter()
+                .map(MatcherConfig::compile)
+                .collect::<Result<Vec<_>, _>>()?;
+            conditions.push(Box::new(any(nested)));
+        }
+
+        let matcher = all(conditions);
+        Ok(if self.negate {
+            Box::new(not(matcher))
+        } else {
+            Box::new(matcher)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+    use http::Extensions;
+
+    fn compile(json: &str) -> Result<Box<dyn Condition>, MatcherConfigError> {
+        serde_json::from_str::<MatcherConfig>(json)
+            .unwrap()
+            .compile()
+    }
+
+    #[test]
+    fn test_compile() {
+        let extensions = Extensions::new();
+        let condition = compile(
+            r#"{
+                "scheme": "https",
+                "route": "/v1/instances/{id}",
+                "methods": ["GET"],
+                "any": [{ "host": "a.example.com" }, { "host": "b.example.com" }]
+            }"#,
+        )
+        .unwrap();
+
+        let req = request(http::Method::GET, "https://b.example.com/v1/instances/1");
+        assert!(condition.evaluate(&req, &extensions));
+        let req = request(http::Method::GET, "https://c.example.com/v1/instances/1");
+        assert!(!condition.evaluate(&req, &extensions));
+        let req = request(http::Method::PUT, "https://a.example.com/v1/instances/1");
+        assert!(!condition.evaluate(&req, &extensions));
+
+        let condition = compile(r#"{ "path_prefix": "/health", "negate": true }"#).unwrap();
+        let req = request(http::Method::GET, "https://a.example.com/health");
+        assert!(!condition.evaluate(&req, &extensions));
+
+        let condition = compile("{}").unwrap();
+        assert!(condition.evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let error = compile(r#"{ "headers": [{ "name": "not a header" }] }"#)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            r#"invalid matcher configuration: invalid header name "not a header""#
+        );
+        assert!(compile(r#"{ "methods": ["GE T"] }"#).is_err());
+        assert!(compile(r#"{ "route": "/{*rest}/x" }"#).is_err());
+        assert!(compile(r#"{ "content_type": "json" }"#).is_err());
+        assert!(serde_json::from_str::<MatcherConfig>(r#"{ "hots": "a" }"#).is_err());
+    }
+}
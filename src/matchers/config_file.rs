@@ -0,0 +1,198 @@
+use http::Extensions;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use reqwest::Request;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::{MatcherConfig, MatcherConfigError};
+use crate::condition::impl_condition_ops;
+use crate::{Condition, ConditionHandle, SwappableCondition};
+
+/// The error returned when a matcher configuration file can not be loaded or watched
+#[derive(Debug)]
+pub enum ConfigFileError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The file is not a JSON [`MatcherConfig`]
+    Parse(serde_json::Error),
+    /// The configuration has invalid values
+    Invalid(MatcherConfigError),
+    /// The file could not be watched for changes
+    Watch(notify::Error),
+}
+
+impl fmt::Display for ConfigFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigFileError::Io(e) => write!(f, "could not read matcher configuration: {e}"),
+            ConfigFileError::Parse(e) => write!(f, "could not parse matcher configuration: {e}"),
+            ConfigFileError::Invalid(e) => e.fmt(f),
+            ConfigFileError::Watch(e) => write!(f, "could not watch matcher configuration: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigFileError::Io(e) => Some(e),
+            ConfigFileError::Parse(e) => Some(e),
+            ConfigFileError::Invalid(e) => Some(e),
+            ConfigFileError::Watch(e) => Some(e),
+        }
+    }
+}
+
+fn load(path: &Path) -> Result<Box<dyn Condition>, ConfigFileError> {
+    let contents = std::fs::read_to_string(path).map_err(ConfigFileError::Io)?;
+    let config: MatcherConfig = serde_json::from_str(&contents).map_err(ConfigFileError::Parse)?;
+    config.compile().map_err(ConfigFileError::Invalid)
+}
+
+fn reload(path: &Path, handle: &ConditionHandle) -> Result<(), ConfigFileError> {
+    handle.set_boxed(load(path)?);
+    tracing::debug!(path = %path.display(), "reloaded matcher configuration");
+    Ok(())
+}
+
+/// A [`Condition`] compiled from a JSON [`MatcherConfig`] file and recompiled whenever the
+/// file changes. Created by [`watch_config_file`]. Clones share the same rules and watcher,
+/// and the file stops being watched once every clone is dropped.
+#[derive(Clone)]
+pub struct ConfigFile {
+    condition: SwappableCondition,
+    handle: ConditionHandle,
+    path: Arc<PathBuf>,
+    _watcher: Arc<Mutex<RecommendedWatcher>>,
+}
+
+impl_condition_ops!(ConfigFile);
+
+/// Loads the JSON [`MatcherConfig`] at `path` and watches it for changes, so that which
+/// requests get a middleware can be retuned without restarting the service. The directory
+/// holding the file is watched, so files replaced by a rename, as editors and configuration
+/// management tools do, are picked up too.
+///
+/// The file must be valid for this to succeed. When a later version can not be loaded, for
+/// instance while it is only partially written, a warning is logged and the previous rules
+/// keep being used until the next change. Available with the `config-file` feature.
+///
+/// ```no_run
+/// use reqwest_conditional_middleware::{matchers, ConditionalMiddleware};
+/// # use reqwest_conditional_middleware::Passthrough as Retry;
+///
+/// let rules = matchers::watch_config_file("/etc/client/retry.json").unwrap();
+/// let conditional = ConditionalMiddleware::new(Retry, rules);
+/// ```
+pub fn watch_config_file(path: impl AsRef<Path>) -> Result<ConfigFile, ConfigFileError> {
+    let path = path.as_ref().to_owned();
+    let condition = SwappableCondition::from_boxed(load(&path)?);
+    let handle = condition.handle();
+
+    let file_name = path.file_name().map(ToOwned::to_owned);
+    let (watched_path, watched_handle) = (path.clone(), handle.clone());
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let event = match event {
+            Ok(event) if !event.kind.is_access() => event,
+            Ok(_) => return,
+            Err(error) => {
+                tracing::warn!(%error, "error watching matcher configuration");
+                return;
+            }
+        };
+        if !event
+            .paths
+            .iter()
+            .any(|changed| changed.file_name() == file_name.as_deref())
+        {
+            return;
+        }
+        if let Err(error) = reload(&watched_path, &watched_handle) {
+            tracing::warn!(
+                path = %watched_path.display(),
+                %error,
+                "keeping previous matcher configuration"
+            );
+        }
+    })
+    .map_err(ConfigFileError::Watch)?;
+
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    watcher
+        .watch(directory, RecursiveMode::NonRecursive)
+        .map_err(ConfigFileError::Watch)?;
+
+    Ok(ConfigFile {
+        condition,
+        handle,
+        path: Arc::new(path),
+        _watcher: Arc::new(Mutex::new(watcher)),
+    })
+}
+
+impl ConfigFile {
+    /// Reloads the file now rather than waiting for a change to be noticed. The previous rules
+    /// are kept if it can not be loaded.
+    pub fn reload(&self) -> Result<(), ConfigFileError> {
+        reload(&self.path, &self.handle)
+    }
+}
+
+impl Condition for ConfigFile {
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        self.condition.evaluate(req, extensions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_watch_config_file() {
+        let extensions = Extensions::new();
+        let req = request(http::Method::GET, "https://api.example.com/v1");
+        let directory = std::env::temp_dir().join(format!("rcm-config-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("rules.json");
+
+        std::fs::write(&path, r#"{"host": "api.example.com"}"#).unwrap();
+        let rules = watch_config_file(&path).unwrap();
+        assert!(rules.evaluate(&req, &extensions));
+
+        // Replace the file by renaming over it, as editors do
+        let staged = directory.join("rules.json.tmp");
+        std::fs::write(&staged, r#"{"host": "other.example.com"}"#).unwrap();
+        std::fs::rename(&staged, &path).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while rules.evaluate(&req, &extensions) {
+            assert!(Instant::now() < deadline, "configuration was not reloaded");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        std::fs::write(&path, r#"{"host": 1}"#).unwrap();
+        assert!(matches!(rules.reload(), Err(ConfigFileError::Parse(_))));
+        assert!(!rules.evaluate(&req, &extensions));
+
+        std::fs::write(&path, r#"{"methods": ["GET"]}"#).unwrap();
+        rules.reload().unwrap();
+        assert!(rules.evaluate(&req, &extensions));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_watch_config_file_errors() {
+        let missing = std::env::temp_dir().join("rcm-config-missing/rules.json");
+        assert!(matches!(
+            watch_config_file(missing),
+            Err(ConfigFileError::Io(_))
+        ));
+    }
+}
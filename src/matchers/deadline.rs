@@ -0,0 +1,174 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+use std::time::{Duration, Instant};
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// The time by which a request must complete, read from the request extensions by
+/// [`budget_at_least`]. Callers can insert it themselves, or have [`SetDeadline`] insert it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Deadline(pub Instant);
+
+/// A middleware giving requests without a [`Deadline`] one a fixed budget from now, or the
+/// request's own timeout when that is shorter. Install it before the conditionals that use
+/// [`budget_at_least`], so that the budget covers the whole middleware stack. A budget too
+/// large to be added to the current time sets no deadline.
+#[derive(Clone, Copy, Debug)]
+pub struct SetDeadline {
+    budget: Duration,
+}
+
+impl SetDeadline {
+    /// Gives requests without a deadline `budget` to complete
+    pub fn new(budget: Duration) -> Self {
+        Self { budget }
+    }
+}
+
+#[async_trait]
+impl Middleware for SetDeadline {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        if extensions.get::<Deadline>().is_none() {
+            let budget = req.timeout().map_or(self.budget, |t| self.budget.min(*t));
+            // A budget too large to represent as an instant is no deadline at all
+            if let Some(deadline) = Instant::now().checked_add(budget) {
+                extensions.insert(Deadline(deadline));
+            }
+        }
+        next.run(req, extensions).await
+    }
+}
+
+/// A [`Condition`] matching requests with enough time left before their deadline. Created by
+/// [`budget_at_least`].
+#[derive(Clone, Copy, Debug)]
+pub struct RemainingBudget(Duration);
+
+impl_condition_ops!(RemainingBudget);
+
+/// Matches requests with at least `threshold` left before their [`Deadline`], so that slow
+/// optional middleware is skipped rather than pushing a request past its deadline. Requests
+/// without a deadline always match.
+///
+/// ```
+/// use reqwest_conditional_middleware::{matchers, ConditionalMiddleware};
+/// use std::time::Duration;
+/// # use reqwest_conditional_middleware::Passthrough as Enrichment;
+///
+/// let conditional = ConditionalMiddleware::new(
+///     Enrichment,
+///     matchers::budget_at_least(Duration::from_millis(200)),
+/// );
+/// ```
+pub fn budget_at_least(threshold: Duration) -> RemainingBudget {
+    RemainingBudget(threshold)
+}
+
+impl Condition for RemainingBudget {
+    fn evaluate(&self, _req: &Request, extensions: &Extensions) -> bool {
+        extensions
+            .get::<Deadline>()
+            .is_none_or(|Deadline(deadline)| {
+                deadline.saturating_duration_since(Instant::now()) >= self.0
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+    use crate::tests::End;
+
+    #[test]
+    fn test_budget_at_least() {
+        let condition = budget_at_least(Duration::from_secs(1));
+        let req = request(http::Method::GET, "http://a/");
+        let mut extensions = Extensions::new();
+
+        assert!(condition.evaluate(&req, &extensions));
+        extensions.insert(Deadline(Instant::now() + Duration::from_secs(60)));
+        assert!(condition.evaluate(&req, &extensions));
+        extensions.insert(Deadline(Instant::now() + Duration::from_millis(500)));
+        assert!(!condition.evaluate(&req, &extensions));
+        extensions.insert(Deadline(Instant::now() - Duration::from_secs(1)));
+        assert!(!condition.evaluate(&req, &extensions));
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    struct Seen(Option<Duration>);
+
+    struct Record;
+
+    #[async_trait]
+    impl Middleware for Record {
+        async fn handle(
+            &self,
+            req: Request,
+            extensions: &mut Extensions,
+            next: Next<'_>,
+        ) -> Result<Response> {
+            let remaining = extensions
+                .get::<Deadline>()
+                .map(|Deadline(deadline)| deadline.saturating_duration_since(Instant::now()));
+            let mut resp = next.run(req, extensions).await?;
+            resp.extensions_mut().insert(Seen(remaining));
+            Ok(resp)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_deadline() {
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(SetDeadline::new(Duration::from_secs(10)))
+                .with(Record)
+                .with(End)
+                .build();
+
+        let resp = client.get("http://localhost").send().await.unwrap();
+        let Seen(remaining) = *resp.extensions().get::<Seen>().unwrap();
+        assert!(remaining.is_some_and(|r| r > Duration::from_secs(9)));
+
+        let resp = client
+            .get("http://localhost")
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await
+            .unwrap();
+        let Seen(remaining) = *resp.extensions().get::<Seen>().unwrap();
+        assert!(remaining.is_some_and(|r| r <= Duration::from_secs(2)));
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let resp = client
+            .get("http://localhost")
+            .with_extension(Deadline(deadline))
+            .send()
+            .await
+            .unwrap();
+        let Seen(remaining) = *resp.extensions().get::<Seen>().unwrap();
+        assert!(remaining.is_some_and(|r| r > Duration::from_secs(50)));
+    }
+
+    #[tokio::test]
+    async fn test_set_deadline_overflow() {
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(SetDeadline::new(Duration::MAX))
+                .with(Record)
+                .with(End)
+                .build();
+
+        let resp = client.get("http://localhost").send().await.unwrap();
+        let Seen(remaining) = *resp.extensions().get::<Seen>().unwrap();
+        assert_eq!(None, remaining);
+    }
+}
@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::Request;
+
+use super::ip::{host_ip, is_internal};
+use crate::{AsyncCondition, Decision};
+
+/// An [`AsyncCondition`] matching requests whose destination resolves to an internal address.
+/// Created by [`resolves_to_internal`].
+#[derive(Clone, Copy, Debug)]
+pub struct InternalDestination;
+
+/// Matches requests whose host resolves to an address that is not publicly routable: private,
+/// shared, loopback, link-local, unique local, reserved or multicast ranges, including IPv4
+/// addresses embedded in IPv6 ones. The host is resolved with the system resolver, and the
+/// request matches when any of its addresses is internal. IP literals are checked without a
+/// lookup.
+///
+/// This lets an SSRF guard run only for requests that would reach internal services. Note that
+/// the lookup is separate from the connection: the client resolves the host again when
+/// connecting and may get a different answer, so a DNS rebinding attack, answering with a
+/// public address here and an internal one to the client, still gets through. Guards that must
+/// hold against rebinding need to check the address actually connected to instead. A
+/// failed lookup is handled by the [`OnConditionError`](crate::OnConditionError) policy of the
+/// middleware; consider bounding lookups with
+/// [`with_timeout`](crate::ConditionalMiddleware::with_timeout).
+///
+/// Available with the `dns` feature.
+///
+/// ```
+/// use reqwest_conditional_middleware::{matchers, ConditionalMiddleware, OnConditionError};
+/// # use reqwest_conditional_middleware::Passthrough as SsrfGuard;
+///
+/// let guard = ConditionalMiddleware::new(SsrfGuard, matchers::resolves_to_internal())
+///     .on_error(OnConditionError::Run);
+/// ```
+pub fn resolves_to_internal() -> InternalDestination {
+    InternalDestination
+}
+
+#[async_trait]
+impl AsyncCondition for InternalDestination {
+    type Error = std::io::Error;
+
+    async fn evaluate_async(
+        &self,
+        req: &Request,
+        _extensions: &Extensions,
+    ) -> Result<Decision, Self::Error> {
+        if let Some(addr) = host_ip(req.url()) {
+            return Ok(is_internal(addr).into());
+        }
+        let Some(host) = req.url().host_str() else {
+            return Ok(Decision::Skip);
+        };
+
+        let port = req.url().port_or_known_default().unwrap_or_default();
+        let mut addrs = tokio::net::lookup_host((host, port)).await?;
+        Ok(addrs.any(|addr| is_internal(addr.ip())).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    async fn internal(url: &str) -> bool {
+        let req = request(http::Method::GET, url);
+        let decision = resolves_to_internal()
+            .evaluate_async(&req, &Extensions::new())
+            .await
+            .unwrap();
+        matches!(decision, Decision::Run)
+    }
+
+    #[tokio::test]
+    async fn test_resolves_to_internal() {
+        assert!(internal("http://localhost:8080/").await);
+        assert!(internal("http://169.254.169.254/latest/meta-data").await);
+        assert!(internal("http://[fd00::1]/").await);
+        assert!(!internal("http://8.8.8.8/").await);
+    }
+}
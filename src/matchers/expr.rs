@@ -0,0 +1,461 @@
+use http::header::HeaderName;
+use http::Extensions;
+use reqwest::Request;
+use std::fmt;
+use std::str::FromStr;
+
+use super::glob::glob_match;
+use super::path::decode_path;
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A part of the request an [`Expression`] can compare
+#[derive(Clone, Debug)]
+enum Field {
+    Method,
+    Scheme,
+    Host,
+    Path,
+    Url,
+    Header(HeaderName),
+    Query(String),
+}
+
+impl Field {
+    /// Calls `f` on each value of the field until it returns true. Headers and query parameters
+    /// may have several values, or none.
+    fn any(&self, req: &Request, mut f: impl FnMut(&str) -> bool) -> bool {
+        match self {
+            Field::Method => f(req.method().as_str()),
+            Field::Scheme => f(req.url().scheme()),
+            Field::Host => req.url().host_str().is_some_and(f),
+            Field::Path => f(&String::from_utf8_lossy(&decode_path(req.url().path()))),
+            Field::Url => f(req.url().as_str()),
+            Field::Header(name) => req
+                .headers()
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .any(f),
+            Field::Query(name) => req
+                .url()
+                .query_pairs()
+                .any(|(key, value)| key == name.as_str() && f(&value)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Glob,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Constant(bool),
+    Compare(Field, Op, String),
+    Not(Box<Node>),
+    // Chains of `&&` and `||` are flat so that long ones do not make the tree deep
+    All(Vec<Node>),
+    Any(Vec<Node>),
+}
+
+impl Node {
+    fn evaluate(&self, req: &Request) -> bool {
+        match self {
+            Node::Constant(value) => *value,
+            Node::Compare(field, Op::Ne, literal) => !Node::compare(field, Op::Eq, literal, req),
+            Node::Compare(field, op, literal) => Node::compare(field, *op, literal, req),
+            Node::Not(node) => !node.evaluate(req),
+            Node::All(nodes) => nodes.iter().all(|node| node.evaluate(req)),
+            Node::Any(nodes) => nodes.iter().any(|node| node.evaluate(req)),
+        }
+    }
+
+    fn compare(field: &Field, op: Op, literal: &str, req: &Request) -> bool {
+        // Hosts are lowercase in URLs, and the literal was lowercased when parsing
+        let ignore_case = matches!(field, Field::Host);
+        field.any(req, |value| match op {
+            Op::Eq | Op::Ne => value == literal,
+            Op::Glob => glob_match(literal.as_bytes(), value.as_bytes(), ignore_case),
+            Op::StartsWith => value.starts_with(literal),
+            Op::EndsWith => value.ends_with(literal),
+            Op::Contains => value.contains(literal),
+        })
+    }
+}
+
+/// The error returned when an [`Expression`] cannot be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpressionError {
+    position: usize,
+    message: String,
+}
+
+impl ExpressionError {
+    /// The byte offset in the expression at which the error was found
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Op(Op),
+}
+
+fn tokenize(src: &str) -> Result<Vec<(usize, Token)>, ExpressionError> {
+    let error = |position, message: &str| ExpressionError {
+        position,
+        message: message.to_owned(),
+    };
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        let two = bytes.get(i..i + 2);
+        let token = match bytes[i] {
+            b if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            _ if two == Some(b"&&") => Token::And,
+            _ if two == Some(b"||") => Token::Or,
+            _ if two == Some(b"==") => Token::Op(Op::Eq),
+            _ if two == Some(b"!=") => Token::Op(Op::Ne),
+            _ if two == Some(b"~=") => Token::Op(Op::Glob),
+            b'!' => Token::Not,
+            b'(' => Token::Open,
+            b')' => Token::Close,
+            b'"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match src[i..].chars().next() {
+                        None => return Err(error(start, "unterminated string")),
+                        Some('"') => break,
+                        Some('\\') => match src[i + 1..].chars().next() {
+                            Some(c @ ('"' | '\\')) => {
+                                value.push(c);
+                                i += 2;
+                            }
+                            _ => return Err(error(i, "invalid escape")),
+                        },
+                        Some(c) => {
+                            value.push(c);
+                            i += c.len_utf8();
+                        }
+                    }
+                }
+                i += 1;
+                tokens.push((start, Token::Str(value)));
+                continue;
+            }
+            b if b.is_ascii_alphanumeric() || b == b'_' => {
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || b"_-.".contains(&bytes[i]))
+                {
+                    i += 1;
+                }
+                tokens.push((start, Token::Ident(src[start..i].to_owned())));
+                continue;
+            }
+            _ => return Err(error(start, "unexpected character")),
+        };
+        i += match token {
+            Token::Not | Token::Open | Token::Close => 1,
+            _ => 2,
+        };
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+/// How deeply `!` and parentheses may nest, bounding the recursion of parsing, evaluating and
+/// dropping an expression so that untrusted input can not overflow the stack
+const MAX_DEPTH: usize = 64;
+
+/// A recursive descent parser over the tokens of an expression
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    len: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn error(&self, message: impl Into<String>) -> ExpressionError {
+        ExpressionError {
+            position: self.tokens.get(self.next).map_or(self.len, |(at, _)| *at),
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let matched = self.peek() == Some(token);
+        if matched {
+            self.next += 1;
+        }
+        matched
+    }
+
+    fn or(&mut self) -> Result<Node, ExpressionError> {
+        let mut nodes = vec![self.and()?];
+        while self.eat(&Token::Or) {
+            nodes.push(self.and()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.pop().unwrap(),
+            _ => Node::Any(nodes),
+        })
+    }
+
+    fn and(&mut self) -> Result<Node, ExpressionError> {
+        let mut nodes = vec![self.unary()?];
+        while self.eat(&Token::And) {
+            nodes.push(self.unary()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.pop().unwrap(),
+            _ => Node::All(nodes),
+        })
+    }
+
+    fn unary(&mut self) -> Result<Node, ExpressionError> {
+        if !matches!(self.peek(), Some(Token::Not | Token::Open)) {
+            return self.comparison();
+        }
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(format!("nested more than {MAX_DEPTH} levels deep")));
+        }
+        self.depth += 1;
+        let node = if self.eat(&Token::Not) {
+            Node::Not(Box::new(self.unary()?))
+        } else {
+            self.next += 1;
+            let node = self.or()?;
+            if !self.eat(&Token::Close) {
+                return Err(self.error("expected `)`"));
+            }
+            node
+        };
+        self.depth -= 1;
+        Ok(node)
+    }
+
+    fn comparison(&mut self) -> Result<Node, ExpressionError> {
+        let Some(Token::Ident(name)) = self.peek().cloned() else {
+            return Err(self.error("expected a field, `!` or `(`"));
+        };
+        let field = match name.as_str() {
+            "true" | "false" => {
+                self.next += 1;
+                return Ok(Node::Constant(name == "true"));
+            }
+            "method" => Field::Method,
+            "scheme" => Field::Scheme,
+            "host" => Field::Host,
+            "path" => Field::Path,
+            "url" => Field::Url,
+            _ => match name.split_once('.') {
+                Some(("header", header)) => match HeaderName::try_from(header) {
+                    Ok(header) => Field::Header(header),
+                    Err(_) => return Err(self.error(format!("invalid header name {header:?}"))),
+                },
+                Some(("query", param)) => Field::Query(param.to_owned()),
+                _ => return Err(self.error(format!("unknown field `{name}`"))),
+            },
+        };
+        self.next += 1;
+
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            Some(Token::Ident(op)) if op == "startswith" => Op::StartsWith,
+            Some(Token::Ident(op)) if op == "endswith" => Op::EndsWith,
+            Some(Token::Ident(op)) if op == "contains" => Op::Contains,
+            _ => return Err(self.error("expected an operator")),
+        };
+        self.next += 1;
+
+        let Some(Token::Str(mut literal)) = self.peek().cloned() else {
+            return Err(self.error("expected a string"));
+        };
+        self.next += 1;
+        if matches!(field, Field::Host) {
+            literal.make_ascii_lowercase();
+        }
+
+        Ok(Node::Compare(field, op, literal))
+    }
+}
+
+/// A [`Condition`] written as a string expression. Created by [`expression`] or by parsing a
+/// string with [`str::parse`].
+#[derive(Clone, Debug)]
+pub struct Expression(Node);
+
+impl_condition_ops!(Expression);
+
+/// Compiles a condition written as a string, so that gating can be configured at runtime.
+///
+/// An expression compares fields of the request against double-quoted strings, and combines
+/// comparisons with `&&`, `||`, `!` and parentheses. `true` and `false` are also expressions.
+///
+/// | Field | Value |
+/// | --- | --- |
+/// | `method` | The method, such as `GET` |
+/// | `scheme` | `http` or `https` |
+/// | `host` | The host, compared case-insensitively |
+/// | `path` | The percent-decoded path |
+/// | `url` | The whole URL |
+/// | `header.<name>` | Any value of the header, if valid UTF-8 |
+/// | `query.<name>` | Any decoded value of the query parameter |
+///
+/// | Operator | Matches when the value |
+/// | --- | --- |
+/// | `==` | equals the string |
+/// | `!=` | does not equal the string, including when the field is missing |
+/// | `~=` | matches the string as a glob, where `*` matches any run of characters and `?` one |
+/// | `startswith` | starts with the string |
+/// | `endswith` | ends with the string |
+/// | `contains` | contains the string |
+///
+/// Strings may contain `\"` and `\\` escapes. Other than `!=`, comparisons on a missing header,
+/// query parameter or host are false.
+/// `!` and parentheses may nest up to 64 levels deep, deeper expressions are rejected.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::expression(
+///     r#"method == "GET" && host ~= "*.example.com" && path startswith "/v1""#,
+/// )
+/// .unwrap();
+/// ```
+pub fn expression(src: &str) -> Result<Expression, ExpressionError> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        next: 0,
+        len: src.len(),
+        depth: 0,
+    };
+    let node = parser.or()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected token"));
+    }
+    Ok(Expression(node))
+}
+
+impl FromStr for Expression {
+    type Err = ExpressionError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        expression(src)
+    }
+}
+
+impl Condition for Expression {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        self.0.evaluate(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    fn matches(src: &str, req: &Request) -> bool {
+        expression(src).unwrap().evaluate(req, &Extensions::new())
+    }
+
+    #[test]
+    fn test_expression() {
+        let mut req = request(
+            http::Method::GET,
+            "https://API.example.com/v1/with%20space?debug=1&tag=a&tag=b",
+        );
+        req.headers_mut()
+            .insert("x-env", http::HeaderValue::from_static("staging"));
+
+        assert!(matches(
+            r#"method == "GET" && host ~= "*.EXAMPLE.com" && path startswith "/v1""#,
+            &req
+        ));
+        assert!(matches(r#"path endswith "with space""#, &req));
+        assert!(matches(r#"url contains "debug=1""#, &req));
+        assert!(matches(r#"scheme == "https" && query.tag == "b""#, &req));
+        assert!(matches(r#"header.X-Env == "staging""#, &req));
+        assert!(matches(r#"header.x-missing != "staging""#, &req));
+        assert!(!matches(r#"header.x-missing startswith """#, &req));
+        assert!(matches(
+            r#"method == "POST" || !(query.debug != "1")"#,
+            &req
+        ));
+        assert!(!matches(r#"method == "GET" && false"#, &req));
+        assert!(matches(r#"true || method == "POST" && false"#, &req));
+        assert!(matches(r#"header.x-env == "stag\"ing" || true"#, &req));
+    }
+
+    #[test]
+    fn test_expression_errors() {
+        let error = |src: &str| expression(src).unwrap_err();
+
+        assert_eq!(error(r#"method = "GET""#).position(), 7);
+        assert_eq!(
+            error(r#"verb == "GET""#).to_string(),
+            "unknown field `verb` at position 0"
+        );
+        assert_eq!(error(r#"method == GET"#).position(), 10);
+        assert_eq!(error(r#"(method == "GET""#).position(), 16);
+        assert_eq!(error(r#"method == "GET"#).position(), 10);
+        assert_eq!(error(r#"method == "GET" true"#).position(), 16);
+        assert!("header.a\u{0}b == \"x\"".parse::<Expression>().is_err());
+    }
+
+    #[test]
+    fn test_expression_depth() {
+        let req = request(http::Method::GET, "http://a/");
+        let nested = |depth: usize| format!("{}true{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(matches(&nested(MAX_DEPTH), &req));
+        assert!(matches(&format!("{}true", "!".repeat(MAX_DEPTH)), &req));
+        assert_eq!(
+            expression(&nested(MAX_DEPTH + 1)).unwrap_err().position(),
+            MAX_DEPTH
+        );
+        assert!(expression(&format!("{}true", "!".repeat(100_000))).is_err());
+        assert!(expression(&nested(100_000)).is_err());
+
+        // Long chains do not nest
+        let chain = vec!["true"; 100_000].join(" && ");
+        assert!(matches(&chain, &req));
+    }
+}
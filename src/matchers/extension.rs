@@ -0,0 +1,78 @@
+use http::Extensions;
+use reqwest::Request;
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] matching requests whose extensions hold a value of a given type. Created by
+/// [`has_extension`].
+pub struct HasExtension<T>(PhantomData<fn() -> T>);
+
+impl<T> Clone for HasExtension<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for HasExtension<T> {}
+
+impl<T> fmt::Debug for HasExtension<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HasExtension")
+            .field(&std::any::type_name::<T>())
+            .finish()
+    }
+}
+
+impl_condition_ops!(HasExtension<T>);
+
+/// Matches requests whose extensions hold a value of type `T`, whatever its value. Use
+/// [`condition::extension`](crate::condition::extension) to also look at the value.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// #[derive(Clone)]
+/// struct SigningContext {
+///     key_id: String,
+/// }
+///
+/// let condition = matchers::has_extension::<SigningContext>();
+/// ```
+pub fn has_extension<T>() -> HasExtension<T>
+where
+    T: Send + Sync + 'static,
+{
+    HasExtension(PhantomData)
+}
+
+impl<T> Condition for HasExtension<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn evaluate(&self, _req: &Request, extensions: &Extensions) -> bool {
+        extensions.get::<T>().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    #[derive(Clone)]
+    struct SigningContext;
+
+    #[test]
+    fn test_has_extension() {
+        let req = request(http::Method::GET, "http://a/");
+        let mut extensions = Extensions::new();
+
+        assert!(!has_extension::<SigningContext>().evaluate(&req, &extensions));
+        extensions.insert(SigningContext);
+        assert!(has_extension::<SigningContext>().evaluate(&req, &extensions));
+        assert!(!has_extension::<u32>().evaluate(&req, &extensions));
+    }
+}
@@ -0,0 +1,70 @@
+/// Matches `text` against a glob `pattern`, where `*` matches any run of characters
+/// (including none) and `?` matches exactly one character. Comparison is ASCII
+/// case-insensitive when `ignore_case` is set.
+///
+/// Works on bytes and does not allocate; a `?` matches a single byte, which is a single
+/// character for the ASCII hosts and URLs it is used on.
+pub(crate) fn glob_match(pattern: &[u8], text: &[u8], ignore_case: bool) -> bool {
+    let eq = |a: u8, b: u8| {
+        if ignore_case {
+            a.eq_ignore_ascii_case(&b)
+        } else {
+            a == b
+        }
+    };
+
+    let (mut p, mut t) = (0, 0);
+    // The position of the last `*` seen in the pattern, and of the text when it was seen
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || eq(c, text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` consume one more character and try again
+                Some((star, star_t)) => {
+                    p = star + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(
+            b"*.svc.cluster.local",
+            b"a.b.svc.cluster.local",
+            false
+        ));
+        assert!(glob_match(
+            b"api-*.example.com",
+            b"api-eu1.example.com",
+            false
+        ));
+        assert!(glob_match(b"api-*.example.com", b"api-.example.com", false));
+        assert!(!glob_match(b"api-*.example.com", b"api.example.com", false));
+        assert!(glob_match(b"a?c", b"abc", false));
+        assert!(!glob_match(b"a?c", b"ac", false));
+        assert!(glob_match(b"*", b"", false));
+        assert!(glob_match(b"**a*", b"bab", false));
+        assert!(!glob_match(b"ABC", b"abc", false));
+        assert!(glob_match(b"ABC", b"abc", true));
+    }
+}
@@ -0,0 +1,257 @@
+use http::Extensions;
+use reqwest::Request;
+use serde_json::Value;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// The type of a GraphQL operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperationType {
+    /// A read-only `query`, including the `{ ... }` shorthand
+    Query,
+    /// A `mutation`
+    Mutation,
+    /// A `subscription`
+    Subscription,
+}
+
+/// Skips whitespace, commas and comments, which GraphQL ignores between tokens
+fn skip_ignored(doc: &[u8], mut i: usize) -> usize {
+    while let Some(&c) = doc.get(i) {
+        match c {
+            b' ' | b'\t' | b'\n' | b'\r' | b',' => i += 1,
+            b'#' => {
+                while doc.get(i).is_some_and(|&c| c != b'\n') {
+                    i += 1;
+                }
+            }
+            _ => break,
+        }
+    }
+    i
+}
+
+/// Skips a string or block string starting at `i`
+fn skip_string(doc: &[u8], mut i: usize) -> usize {
+    if doc[i..].starts_with(b"\"\"\"") {
+        i += 3;
+        while i < doc.len() && !doc[i..].starts_with(b"\"\"\"") {
+            i += if doc[i..].starts_with(b"\\\"\"\"") {
+                4
+            } else {
+                1
+            };
+        }
+        return i + 3;
+    }
+    i += 1;
+    while let Some(&c) = doc.get(i) {
+        i += 1;
+        match c {
+            b'\\' => i += 1,
+            b'"' => break,
+            _ => {}
+        }
+    }
+    i
+}
+
+/// Skips from `i` past the end of the next top-level selection set, ignoring braces in strings
+/// and in parenthesised variable definitions
+fn skip_definition(doc: &[u8], mut i: usize) -> usize {
+    let (mut braces, mut parens) = (0usize, 0usize);
+    while let Some(&c) = doc.get(i) {
+        match c {
+            b'"' => {
+                i = skip_string(doc, i);
+                continue;
+            }
+            b'#' => {
+                i = skip_ignored(doc, i);
+                continue;
+            }
+            b'(' => parens += 1,
+            b')' => parens = parens.saturating_sub(1),
+            b'{' => braces += 1,
+            b'}' => {
+                braces = braces.saturating_sub(1);
+                if braces == 0 && parens == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+fn name(doc: &[u8], i: usize) -> &[u8] {
+    let len = doc[i..]
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == b'_')
+        .count();
+    &doc[i..i + len]
+}
+
+/// Returns the type of the operation named `operation_name` in a GraphQL document, or of its
+/// only operation when no name is given
+fn operation_type(doc: &str, operation_name: Option<&str>) -> Option<OperationType> {
+    let doc = doc.as_bytes();
+    let mut i = skip_ignored(doc, 0);
+    let mut found = None;
+
+    while i < doc.len() {
+        let (kind, op_name) = if doc[i] == b'{' {
+            (Some(OperationType::Query), &b""[..])
+        } else {
+            let keyword = name(doc, i);
+            let kind = match keyword {
+                b"query" => Some(OperationType::Query),
+                b"mutation" => Some(OperationType::Mutation),
+                b"subscription" => Some(OperationType::Subscription),
+                // Fragments and type system definitions are not operations
+                b"" => return None,
+                _ => None,
+            };
+            let after = skip_ignored(doc, i + keyword.len());
+            (kind, name(doc, after))
+        };
+
+        if let Some(kind) = kind {
+            match operation_name {
+                Some(wanted) if wanted.as_bytes() == op_name => return Some(kind),
+                Some(_) => {}
+                // Without a name the document must hold a single operation
+                None if found.is_some() => return None,
+                None => found = Some(kind),
+            }
+        }
+        i = skip_ignored(doc, skip_definition(doc, i));
+    }
+
+    found
+}
+
+/// Returns the operation type of a GraphQL request body holding a single request
+fn body_operation(request: &Value) -> Option<OperationType> {
+    let query = request.get("query")?.as_str()?;
+    let operation_name = request.get("operationName").and_then(Value::as_str);
+    operation_type(query, operation_name)
+}
+
+/// A [`Condition`] matching GraphQL requests by the type of their operation. Created by
+/// [`graphql_operation`].
+#[derive(Clone, Copy, Debug)]
+pub struct GraphqlOperation(OperationType);
+
+impl_condition_ops!(GraphqlOperation);
+
+/// Matches GraphQL requests whose operation is of type `kind`, so that for instance retries are
+/// only enabled for queries. The operation is found by peeking at the buffered JSON body, and its
+/// `operationName` when the document holds several operations. A batch of requests matches when
+/// all of its operations do. `GET` requests carry the document in the `query` parameter instead.
+///
+/// Requests that are not GraphQL, have a streaming body, or whose operation can not be
+/// determined never match. Available with the `graphql` feature.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers::{self, OperationType};
+///
+/// let retryable = matchers::graphql_operation(OperationType::Query);
+/// ```
+pub fn graphql_operation(kind: OperationType) -> GraphqlOperation {
+    GraphqlOperation(kind)
+}
+
+impl Condition for GraphqlOperation {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        if req.method() == http::Method::GET {
+            let mut query = None;
+            let mut operation_name = None;
+            for (key, value) in req.url().query_pairs() {
+                match &*key {
+                    "query" => query = Some(value),
+                    "operationName" => operation_name = Some(value),
+                    _ => {}
+                }
+            }
+            return query.is_some_and(|query| {
+                operation_type(&query, operation_name.as_deref()) == Some(self.0)
+            });
+        }
+
+        let Some(body) = req.body().and_then(|body| body.as_bytes()) else {
+            return false;
+        };
+        match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Array(batch)) => {
+                !batch.is_empty()
+                    && batch
+                        .iter()
+                        .all(|request| body_operation(request) == Some(self.0))
+            }
+            Ok(request) => body_operation(&request) == Some(self.0),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    fn post(body: &'static str) -> Request {
+        let mut req = request(http::Method::POST, "http://a/graphql");
+        *req.body_mut() = Some(body.into());
+        req
+    }
+
+    #[test]
+    fn test_operation_type() {
+        assert_eq!(
+            operation_type("{ me { id } }", None),
+            Some(OperationType::Query)
+        );
+        assert_eq!(
+            operation_type("# comment\nmutation Up($a: In = {x: \"}\"}) { up }", None),
+            Some(OperationType::Mutation)
+        );
+        let doc = r#"
+            query Get { item(id: "{") { ...F } }
+            fragment F on Item { id }
+            mutation Set { set(v: """ } """) }
+        "#;
+        assert_eq!(operation_type(doc, None), None);
+        assert_eq!(operation_type(doc, Some("Get")), Some(OperationType::Query));
+        assert_eq!(
+            operation_type(doc, Some("Set")),
+            Some(OperationType::Mutation)
+        );
+        assert_eq!(operation_type(doc, Some("Del")), None);
+        assert_eq!(operation_type("", None), None);
+    }
+
+    #[test]
+    fn test_graphql_operation() {
+        let extensions = Extensions::new();
+        let queries = graphql_operation(OperationType::Query);
+
+        assert!(queries.evaluate(&post(r#"{"query": "query { me { id } }"}"#), &extensions));
+        assert!(!queries.evaluate(&post(r#"{"query": "mutation { up }"}"#), &extensions));
+        assert!(queries.evaluate(
+            &post(r#"[{"query": "{ a }"}, {"query": "query B { b }"}]"#),
+            &extensions
+        ));
+        assert!(!queries.evaluate(
+            &post(r#"[{"query": "{ a }"}, {"query": "mutation { b }"}]"#),
+            &extensions
+        ));
+        assert!(!queries.evaluate(&post("not json"), &extensions));
+
+        let get = request(http::Method::GET, "http://a/graphql?query=%7B%20me%20%7D");
+        assert!(queries.evaluate(&get, &extensions));
+    }
+}
@@ -0,0 +1,737 @@
+use http::header::HeaderName;
+use http::Extensions;
+use reqwest::Request;
+use std::fmt::Debug;
+
+use super::glob::glob_match;
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// Converts a header name given to a matcher constructor, panicking on invalid names
+pub(crate) fn header_name<N>(name: N) -> HeaderName
+where
+    N: TryInto<HeaderName>,
+    N::Error: Debug,
+{
+    name.try_into().expect("invalid header name")
+}
+
+/// Splits a media type such as `text/html; charset=utf-8` into its type and subtype, ignoring
+/// any parameters
+pub(crate) fn parse_media_type(value: &str) -> Option<(&str, &str)> {
+    let essence = value.split(';').next()?;
+    let (kind, subtype) = essence.split_once('/')?;
+    let (kind, subtype) = (kind.trim(), subtype.trim());
+    (!kind.is_empty() && !subtype.is_empty()).then_some((kind, subtype))
+}
+
+/// Returns the `q` parameter of a media range, defaulting to 1
+fn quality(range: &str) -> f32 {
+    range
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(1.0)
+}
+
+/// A [`Condition`] matching requests carrying a header. Created by [`has_header`].
+#[derive(Clone, Debug)]
+pub struct HasHeader(HeaderName);
+
+impl_condition_ops!(HasHeader);
+
+/// Matches requests with at least one `name` header, whatever its value. Negate it to match
+/// requests missing the header.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let missing_request_id = !matchers::has_header("x-request-id");
+/// let authenticated = matchers::has_header(http::header::AUTHORIZATION);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `name` is not a valid header name.
+pub fn has_header<N>(name: N) -> HasHeader
+where
+    N: TryInto<HeaderName>,
+    N::Error: Debug,
+{
+    HasHeader(header_name(name))
+}
+
+impl Condition for HasHeader {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.headers().contains_key(&self.0)
+    }
+}
+
+/// Matches requests carrying an `Idempotency-Key` header, which callers use to mark a request as
+/// safe to replay even when its method is not idempotent. Typically used to enable retries.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers::{self, Methods};
+///
+/// let replayable = Methods::idempotent() | matchers::has_idempotency_key();
+/// ```
+pub fn has_idempotency_key() -> HasHeader {
+    HasHeader(HeaderName::from_static("idempotency-key"))
+}
+
+/// Matches range requests, which carry a `Range` header asking for part of a resource. Negate
+/// it to skip caching or checksum middleware that only understands full responses.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let full_download = !matchers::is_range_request();
+/// ```
+pub fn is_range_request() -> HasHeader {
+    HasHeader(http::header::RANGE)
+}
+
+/// A [`Condition`] matching requests that revalidate a cached response. Created by
+/// [`is_revalidation`].
+#[derive(Clone, Copy, Debug)]
+pub struct Revalidation;
+
+impl_condition_ops!(Revalidation);
+
+/// Matches requests carrying an `If-None-Match` or `If-Modified-Since` validator, meaning the
+/// caller is already revalidating a response it has cached. Negate it to bypass a caching
+/// middleware in that case.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let cacheable = !matchers::is_revalidation();
+/// ```
+pub fn is_revalidation() -> Revalidation {
+    Revalidation
+}
+
+impl Condition for Revalidation {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        let headers = req.headers();
+        headers.contains_key(http::header::IF_NONE_MATCH)
+            || headers.contains_key(http::header::IF_MODIFIED_SINCE)
+    }
+}
+
+/// A [`Condition`] matching requests with a header set to a value. Created by [`header_eq`].
+#[derive(Clone, Debug)]
+pub struct HeaderEq {
+    name: HeaderName,
+    value: String,
+}
+
+impl_condition_ops!(HeaderEq);
+
+/// Matches requests with a `name` header whose value is exactly `value`. When the header is
+/// sent more than once any of its values may match; values are not split on commas.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::header_eq("x-environment", "staging");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `name` is not a valid header name.
+pub fn header_eq<N>(name: N, value: impl Into<String>) -> HeaderEq
+where
+    N: TryInto<HeaderName>,
+    N::Error: Debug,
+{
+    HeaderEq {
+        name: header_name(name),
+        value: value.into(),
+    }
+}
+
+impl Condition for HeaderEq {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.headers()
+            .get_all(&self.name)
+            .iter()
+            .any(|value| value.as_bytes() == self.value.as_bytes())
+    }
+}
+
+/// A [`Condition`] matching requests by the media type of their body. Created by
+/// [`content_type`].
+#[derive(Clone, Debug)]
+pub struct ContentType {
+    kind: String,
+    subtype: String,
+}
+
+impl_condition_ops!(ContentType);
+
+/// Matches requests whose `Content-Type` header has the media type `media_type`. The type and
+/// subtype are compared case-insensitively and parameters such as `charset` are ignored, so
+/// `content_type("application/json")` matches `application/json; charset=utf-8`. A `*`
+/// subtype, as in `image/*`, matches any subtype. Requests without a `Content-Type` do not
+/// match.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let json = matchers::content_type("application/json");
+/// let not_multipart = !matchers::content_type("multipart/*");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `media_type` is not of the form `type/subtype`.
+pub fn content_type(media_type: &str) -> ContentType {
+    let (kind, subtype) = parse_media_type(media_type).expect("invalid media type");
+    ContentType {
+        kind: kind.to_ascii_lowercase(),
+        subtype: subtype.to_ascii_lowercase(),
+    }
+}
+
+/// Matches multipart requests, such as `multipart/form-data` uploads built with reqwest's
+/// `multipart` feature, by their `Content-Type`. Any `multipart/*` subtype matches, since
+/// middleware that rewrites or signs bodies usually can't handle any of them. Negate it to skip
+/// such middleware for uploads.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let not_upload = !matchers::is_multipart();
+/// ```
+pub fn is_multipart() -> ContentType {
+    ContentType {
+        kind: "multipart".to_owned(),
+        subtype: "*".to_owned(),
+    }
+}
+
+impl Condition for ContentType {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        let content_type = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_media_type);
+
+        content_type.is_some_and(|(kind, subtype)| {
+            kind.eq_ignore_ascii_case(&self.kind)
+                && (self.subtype == "*" || subtype.eq_ignore_ascii_case(&self.subtype))
+        })
+    }
+}
+
+/// A [`Condition`] matching gRPC requests. Created by [`is_grpc`].
+#[derive(Clone, Copy, Debug)]
+pub struct Grpc;
+
+impl_condition_ops!(Grpc);
+
+/// Matches gRPC and gRPC-Web requests by their `Content-Type`: `application/grpc`,
+/// `application/grpc-web` and `application/grpc-web-text`, with or without a message format
+/// suffix such as `+proto`. Negate it to skip HTTP-specific middleware, such as caching or body
+/// rewriting, for gRPC calls sharing the same client.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let not_grpc = !matchers::is_grpc();
+/// ```
+pub fn is_grpc() -> Grpc {
+    Grpc
+}
+
+impl Condition for Grpc {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        let content_type = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_media_type);
+
+        content_type.is_some_and(|(kind, subtype)| {
+            let subtype = subtype.split('+').next().unwrap_or_default();
+            kind.eq_ignore_ascii_case("application")
+                && ["grpc", "grpc-web", "grpc-web-text"]
+                    .iter()
+                    .any(|grpc| subtype.eq_ignore_ascii_case(grpc))
+        })
+    }
+}
+
+/// Returns true if any `name` header lists `token` in its comma-separated values, compared
+/// case-insensitively and ignoring parameters
+fn lists_token(req: &Request, name: HeaderName, token: &str) -> bool {
+    req.headers()
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|item| item.split(';').next())
+        .any(|item| item.trim().eq_ignore_ascii_case(token))
+}
+
+/// A [`Condition`] matching requests asking to upgrade the connection. Created by
+/// [`is_upgrade`].
+#[derive(Clone, Copy, Debug)]
+pub struct Upgrade;
+
+impl_condition_ops!(Upgrade);
+
+/// Matches requests asking to switch protocols, such as WebSocket handshakes, whose
+/// `Connection` header lists `upgrade`. Buffering or transforming middleware should be bypassed
+/// for these long-lived connections.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let long_lived = matchers::is_upgrade() | matchers::is_event_stream();
+/// ```
+pub fn is_upgrade() -> Upgrade {
+    Upgrade
+}
+
+impl Condition for Upgrade {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        lists_token(req, http::header::CONNECTION, "upgrade")
+    }
+}
+
+/// A [`Condition`] matching requests for a server-sent event stream. Created by
+/// [`is_event_stream`].
+#[derive(Clone, Copy, Debug)]
+pub struct EventStream;
+
+impl_condition_ops!(EventStream);
+
+/// Matches requests whose `Accept` header explicitly lists `text/event-stream`, that is
+/// requests opening a stream of server-sent events. Unlike [`accepts`], a missing `Accept`
+/// header or a wildcard range does not match.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::is_event_stream();
+/// ```
+pub fn is_event_stream() -> EventStream {
+    EventStream
+}
+
+impl Condition for EventStream {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        lists_token(req, http::header::ACCEPT, "text/event-stream")
+    }
+}
+
+/// A [`Condition`] matching requests that accept a media type in response. Created by
+/// [`accepts`].
+#[derive(Clone, Debug)]
+pub struct Accepts {
+    kind: String,
+    subtype: String,
+}
+
+impl_condition_ops!(Accepts);
+
+/// Matches requests whose `Accept` header includes the media type `media_type`, either
+/// directly or through a `type/*` or `*/*` range. Following
+/// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-12.5.1) the most specific
+/// matching range decides, and a range with `q=0` marks the type as not acceptable:
+/// `text/*, text/csv;q=0` accepts `text/plain` but not `text/csv`. A request without an
+/// `Accept` header accepts any media type.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::accepts("application/json");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `media_type` is not of the form `type/subtype`.
+pub fn accepts(media_type: &str) -> Accepts {
+    let (kind, subtype) = parse_media_type(media_type).expect("invalid media type");
+    Accepts {
+        kind: kind.to_ascii_lowercase(),
+        subtype: subtype.to_ascii_lowercase(),
+    }
+}
+
+impl Accepts {
+    /// Returns how specifically `range` matches this media type, if it does
+    fn specificity(&self, range: &str) -> Option<u8> {
+        let (kind, subtype) = parse_media_type(range)?;
+        match (kind, subtype) {
+            ("*", "*") => Some(0),
+            (kind, "*") if kind.eq_ignore_ascii_case(&self.kind) => Some(1),
+            (kind, subtype)
+                if kind.eq_ignore_ascii_case(&self.kind)
+                    && subtype.eq_ignore_ascii_case(&self.subtype) =>
+            {
+                Some(2)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Condition for Accepts {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        let mut headers = req
+            .headers()
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .peekable();
+        if headers.peek().is_none() {
+            return true;
+        }
+
+        let best = headers
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|range| Some((self.specificity(range)?, quality(range))))
+            .fold(None, |best: Option<(u8, f32)>, candidate| match best {
+                Some(best) if best.0 > candidate.0 => Some(best),
+                // Among equally specific ranges, prefer the lowest quality
+                Some(best) if best.0 == candidate.0 && best.1 <= candidate.1 => Some(best),
+                _ => Some(candidate),
+            });
+
+        best.is_some_and(|(_, quality)| quality > 0.0)
+    }
+}
+
+/// A [`Condition`] matching requests that already carry credentials. Created by
+/// [`has_authorization`] and [`authorization_scheme`].
+#[derive(Clone, Debug)]
+pub struct Authorization {
+    scheme: Option<String>,
+}
+
+impl_condition_ops!(Authorization);
+
+/// Matches requests with an `Authorization` header using any scheme. Negate it so that
+/// credential-injecting middleware is skipped when the caller already set credentials.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let needs_credentials = !matchers::has_authorization();
+/// ```
+pub fn has_authorization() -> Authorization {
+    Authorization { scheme: None }
+}
+
+/// Matches requests with an `Authorization` header using the authentication scheme `scheme`,
+/// such as `Bearer` or `Basic`, compared case-insensitively.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::authorization_scheme("Bearer");
+/// ```
+pub fn authorization_scheme(scheme: impl Into<String>) -> Authorization {
+    Authorization {
+        scheme: Some(scheme.into()),
+    }
+}
+
+impl Condition for Authorization {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        let Some(value) = req.headers().get(http::header::AUTHORIZATION) else {
+            return false;
+        };
+        let Some(expected) = &self.scheme else {
+            return true;
+        };
+
+        let scheme = value
+            .as_bytes()
+            .split(|&b| b == b' ')
+            .next()
+            .unwrap_or_default();
+        scheme.eq_ignore_ascii_case(expected.as_bytes())
+    }
+}
+
+/// A [`Condition`] matching requests sending a cookie. Created by [`has_cookie`].
+#[derive(Clone, Debug)]
+pub struct HasCookie(String);
+
+impl_condition_ops!(HasCookie);
+
+/// Matches requests whose `Cookie` header contains a cookie called `name`, compared
+/// case-sensitively. Every `name=value` pair of every `Cookie` header is considered.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::has_cookie("session");
+/// ```
+pub fn has_cookie(name: impl Into<String>) -> HasCookie {
+    HasCookie(name.into())
+}
+
+impl Condition for HasCookie {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.headers()
+            .get_all(http::header::COOKIE)
+            .iter()
+            .flat_map(|value| value.as_bytes().split(|&b| b == b';'))
+            .filter_map(|pair| pair.splitn(2, |&b| b == b'=').next())
+            .any(|name| name.trim_ascii() == self.0.as_bytes())
+    }
+}
+
+#[derive(Clone, Debug)]
+enum UserAgentPattern {
+    Contains(String),
+    Glob(String),
+}
+
+/// A [`Condition`] matching requests by their `User-Agent` header. Created by
+/// [`user_agent_contains`] and [`user_agent_glob`].
+#[derive(Clone, Debug)]
+pub struct UserAgent(UserAgentPattern);
+
+impl_condition_ops!(UserAgent);
+
+/// Matches requests whose `User-Agent` header contains `needle`, compared case-sensitively.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let from_cli = matchers::user_agent_contains("oxide-cli/");
+/// ```
+pub fn user_agent_contains(needle: impl Into<String>) -> UserAgent {
+    UserAgent(UserAgentPattern::Contains(needle.into()))
+}
+
+/// Matches requests whose whole `User-Agent` header matches the glob `pattern`, where `*`
+/// matches any sequence of characters and `?` any single character. For regular expressions
+/// use [`header_regex`](super::header_regex) on [`USER_AGENT`](http::header::USER_AGENT).
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::user_agent_glob("oxide-sdk/0.* (*)");
+/// ```
+pub fn user_agent_glob(pattern: impl Into<String>) -> UserAgent {
+    UserAgent(UserAgentPattern::Glob(pattern.into()))
+}
+
+impl Condition for UserAgent {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        let Some(user_agent) = req.headers().get(http::header::USER_AGENT) else {
+            return false;
+        };
+        let user_agent = user_agent.as_bytes();
+
+        match &self.0 {
+            UserAgentPattern::Contains(needle) => {
+                needle.is_empty()
+                    || user_agent
+                        .windows(needle.len())
+                        .any(|window| window == needle.as_bytes())
+            }
+            UserAgentPattern::Glob(pattern) => glob_match(pattern.as_bytes(), user_agent, false),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    pub(crate) fn with_headers(headers: &[(&'static str, &'static str)]) -> Request {
+        let mut req = request(http::Method::GET, "http://a/");
+        for (name, value) in headers {
+            req.headers_mut()
+                .append(*name, http::HeaderValue::from_static(value));
+        }
+        req
+    }
+
+    #[test]
+    fn test_has_header() {
+        let extensions = Extensions::new();
+        let req = with_headers(&[("x-request-id", "1")]);
+
+        assert!(has_header("X-Request-Id").evaluate(&req, &extensions));
+        assert!(!has_header(http::header::AUTHORIZATION).evaluate(&req, &extensions));
+        assert!((!has_header("x-trace")).evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_has_idempotency_key() {
+        let extensions = Extensions::new();
+
+        let req = with_headers(&[("Idempotency-Key", "8e03978e-40d5-43e8")]);
+        assert!(has_idempotency_key().evaluate(&req, &extensions));
+        assert!(!has_idempotency_key().evaluate(&with_headers(&[]), &extensions));
+    }
+
+    #[test]
+    fn test_is_range_request() {
+        let extensions = Extensions::new();
+
+        let req = with_headers(&[("range", "bytes=0-1023")]);
+        assert!(is_range_request().evaluate(&req, &extensions));
+        assert!(!is_range_request().evaluate(&with_headers(&[]), &extensions));
+    }
+
+    #[test]
+    fn test_is_revalidation() {
+        let extensions = Extensions::new();
+        let etag = with_headers(&[("if-none-match", "\"33a64df5\"")]);
+        let date = with_headers(&[("if-modified-since", "Wed, 21 Oct 2015 07:28:00 GMT")]);
+        let lock = with_headers(&[("if-match", "\"33a64df5\"")]);
+
+        assert!(is_revalidation().evaluate(&etag, &extensions));
+        assert!(is_revalidation().evaluate(&date, &extensions));
+        assert!(!is_revalidation().evaluate(&lock, &extensions));
+    }
+
+    #[test]
+    fn test_header_eq() {
+        let extensions = Extensions::new();
+        let req = with_headers(&[("x-environment", "prod"), ("x-environment", "staging")]);
+
+        assert!(header_eq("x-environment", "staging").evaluate(&req, &extensions));
+        assert!(!header_eq("x-environment", "Staging").evaluate(&req, &extensions));
+        assert!(!header_eq("x-environment", "prod, staging").evaluate(&req, &extensions));
+        assert!(!header_eq("x-other", "staging").evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_content_type() {
+        let extensions = Extensions::new();
+        let json = with_headers(&[("content-type", "Application/JSON ; charset=utf-8")]);
+        let upload = with_headers(&[("content-type", "multipart/form-data; boundary=x")]);
+
+        assert!(content_type("application/json").evaluate(&json, &extensions));
+        assert!(!content_type("application/json").evaluate(&upload, &extensions));
+        assert!(content_type("multipart/*").evaluate(&upload, &extensions));
+        assert!(!content_type("text/plain").evaluate(&with_headers(&[]), &extensions));
+    }
+
+    #[test]
+    fn test_is_multipart() {
+        let extensions = Extensions::new();
+        let upload = with_headers(&[("content-type", "multipart/form-data; boundary=x")]);
+        let json = with_headers(&[("content-type", "application/json")]);
+
+        assert!(is_multipart().evaluate(&upload, &extensions));
+        assert!(!is_multipart().evaluate(&json, &extensions));
+        assert!(!is_multipart().evaluate(&with_headers(&[]), &extensions));
+    }
+
+    #[test]
+    fn test_is_grpc() {
+        let extensions = Extensions::new();
+        for grpc in [
+            "application/grpc",
+            "application/grpc+proto",
+            "application/grpc-web+json",
+            "Application/gRPC-Web-Text",
+        ] {
+            let req = with_headers(&[("content-type", grpc)]);
+            assert!(is_grpc().evaluate(&req, &extensions), "{grpc}");
+        }
+        for other in ["application/json", "application/grpcx", "text/grpc"] {
+            let req = with_headers(&[("content-type", other)]);
+            assert!(!is_grpc().evaluate(&req, &extensions), "{other}");
+        }
+    }
+
+    #[test]
+    fn test_long_lived() {
+        let extensions = Extensions::new();
+        let websocket = with_headers(&[
+            ("connection", "keep-alive, Upgrade"),
+            ("upgrade", "websocket"),
+        ]);
+        let events = with_headers(&[("accept", "text/event-stream;q=1, */*;q=0.1")]);
+        let plain = with_headers(&[("connection", "keep-alive"), ("accept", "*/*")]);
+
+        assert!(is_upgrade().evaluate(&websocket, &extensions));
+        assert!(!is_upgrade().evaluate(&plain, &extensions));
+        assert!(is_event_stream().evaluate(&events, &extensions));
+        assert!(!is_event_stream().evaluate(&plain, &extensions));
+        assert!(!is_event_stream().evaluate(&with_headers(&[]), &extensions));
+    }
+
+    #[test]
+    fn test_accepts() {
+        let extensions = Extensions::new();
+        let req = with_headers(&[
+            ("accept", "text/*;q=0.5, text/csv; q=0"),
+            ("accept", "application/json"),
+        ]);
+
+        assert!(accepts("application/json").evaluate(&req, &extensions));
+        assert!(accepts("Text/Plain").evaluate(&req, &extensions));
+        assert!(!accepts("text/csv").evaluate(&req, &extensions));
+        assert!(!accepts("image/png").evaluate(&req, &extensions));
+        assert!(accepts("image/png").evaluate(&with_headers(&[]), &extensions));
+
+        let req = with_headers(&[("accept", "*/*;q=0.1, image/*;q=0")]);
+        assert!(accepts("text/html").evaluate(&req, &extensions));
+        assert!(!accepts("image/png").evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_authorization() {
+        let extensions = Extensions::new();
+        let bearer = with_headers(&[("authorization", "bearer abc.def")]);
+        let anonymous = with_headers(&[]);
+
+        assert!(has_authorization().evaluate(&bearer, &extensions));
+        assert!(!has_authorization().evaluate(&anonymous, &extensions));
+        assert!(authorization_scheme("Bearer").evaluate(&bearer, &extensions));
+        assert!(!authorization_scheme("Basic").evaluate(&bearer, &extensions));
+        assert!(!authorization_scheme("Bear").evaluate(&bearer, &extensions));
+        assert!(!authorization_scheme("Bearer").evaluate(&anonymous, &extensions));
+    }
+
+    #[test]
+    fn test_has_cookie() {
+        let extensions = Extensions::new();
+        let req = with_headers(&[
+            ("cookie", "theme=dark; session_id=1"),
+            ("cookie", "session=abc=def"),
+        ]);
+
+        assert!(has_cookie("theme").evaluate(&req, &extensions));
+        assert!(has_cookie("session").evaluate(&req, &extensions));
+        assert!(has_cookie("session_id").evaluate(&req, &extensions));
+        assert!(!has_cookie("Session").evaluate(&req, &extensions));
+        assert!(!has_cookie("dark").evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_user_agent() {
+        let extensions = Extensions::new();
+        let req = with_headers(&[("user-agent", "oxide-sdk/0.7.1 (billing) reqwest/0.12")]);
+
+        assert!(user_agent_contains("(billing)").evaluate(&req, &extensions));
+        assert!(!user_agent_contains("(Billing)").evaluate(&req, &extensions));
+        assert!(user_agent_glob("oxide-sdk/0.* (billing)*").evaluate(&req, &extensions));
+        assert!(!user_agent_glob("oxide-sdk/1.*").evaluate(&req, &extensions));
+        assert!(!user_agent_contains("oxide").evaluate(&with_headers(&[]), &extensions));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid header name")]
+    fn test_invalid_header_name() {
+        has_header("not a header");
+    }
+}
@@ -0,0 +1,250 @@
+use http::Extensions;
+use reqwest::Request;
+
+use super::glob::glob_match;
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// Splits a `host[:port]` pattern, keeping the brackets around IPv6 literals so that the host
+/// compares equal to [`Url::host_str`][reqwest::Url::host_str]
+///
+/// # Panics
+///
+/// Panics if the pattern is an IPv6 literal without brackets, whose last group would otherwise
+/// be taken for a port.
+pub(super) fn split_port(pattern: &str) -> (&str, Option<u16>) {
+    assert!(
+        pattern.contains(']') || pattern.matches(':').count() < 2,
+        "IPv6 literals in host patterns must be bracketed, as in `[::1]`: {pattern}"
+    );
+    let port_start = match pattern.rfind(']') {
+        Some(end) => pattern[end..].find(':').map(|colon| end + colon),
+        None => pattern.rfind(':'),
+    };
+
+    match port_start.map(|colon| (&pattern[..colon], pattern[colon + 1..].parse())) {
+        Some((host, Ok(port))) => (host, Some(port)),
+        _ => (pattern, None),
+    }
+}
+
+/// Returns true if the request targets `port`, taking the scheme's default port into account
+/// when the URL does not have one. Matches any port when `port` is `None`.
+fn port_matches(req: &Request, port: Option<u16>) -> bool {
+    port.is_none() || req.url().port_or_known_default() == port
+}
+
+/// A [`Condition`] matching requests to an exact host. Created by [`host`].
+#[derive(Clone, Debug)]
+pub struct Host {
+    host: String,
+    port: Option<u16>,
+}
+
+impl_condition_ops!(Host);
+
+/// Matches requests whose URL host is `pattern`, compared case-insensitively and without the
+/// trailing dot of fully qualified names. The pattern may include a port
+/// (`api.oxide.computer:8443`), in which case the request must also target that port; a URL
+/// without an explicit port targets the default port of its scheme. Without a port in the
+/// pattern requests to any port match.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::host("api.oxide.computer");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `pattern` is an IPv6 literal without brackets, such as `::1` rather than `[::1]`.
+pub fn host(pattern: &str) -> Host {
+    let (host, port) = split_port(pattern);
+    Host {
+        host: host.trim_end_matches('.').to_ascii_lowercase(),
+        port,
+    }
+}
+
+impl Condition for Host {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.url().host_str().is_some_and(|host| {
+            let host = host.strip_suffix('.').unwrap_or(host);
+            host.eq_ignore_ascii_case(&self.host)
+        }) && port_matches(req, self.port)
+    }
+}
+
+/// A [`Condition`] matching requests to a domain or its subdomains. Created by [`domain`].
+#[derive(Clone, Debug)]
+pub struct Domain {
+    domain: String,
+    include_self: bool,
+}
+
+impl_condition_ops!(Domain);
+
+/// Matches requests whose URL host is `domain` or one of its subdomains, compared
+/// case-insensitively and on label boundaries: `domain("example.com")` matches
+/// `example.com` and `api.example.com` but not `evil-example.com`. With a leading dot
+/// (`.example.com`) only subdomains match.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::domain(".internal.example.com");
+/// ```
+pub fn domain(domain: &str) -> Domain {
+    let (domain, include_self) = match domain.strip_prefix('.') {
+        Some(domain) => (domain, false),
+        None => (domain, true),
+    };
+    Domain {
+        domain: domain.trim_end_matches('.').to_ascii_lowercase(),
+        include_self,
+    }
+}
+
+impl Condition for Domain {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        let Some(host) = req.url().host_str() else {
+            return false;
+        };
+        let host = host.strip_suffix('.').unwrap_or(host);
+
+        if host.len() == self.domain.len() {
+            return self.include_self && host.eq_ignore_ascii_case(&self.domain);
+        }
+
+        // Compare bytes so that a suffix starting inside a multi-byte character can not panic
+        let suffix_start = match host.len().checked_sub(self.domain.len() + 1) {
+            Some(start) => start,
+            None => return false,
+        };
+        let (label_end, suffix) = host.as_bytes()[suffix_start..].split_at(1);
+        label_end == b"." && suffix.eq_ignore_ascii_case(self.domain.as_bytes())
+    }
+}
+
+/// A [`Condition`] matching requests whose host matches a glob pattern. Created by
+/// [`host_glob`].
+#[derive(Clone, Debug)]
+pub struct HostGlob {
+    pattern: String,
+    port: Option<u16>,
+}
+
+impl_condition_ops!(HostGlob);
+
+/// Matches requests whose URL host matches the glob `pattern`, compared case-insensitively.
+/// `*` matches any run of characters, including dots, and `?` matches a single character, so
+/// `*.svc.cluster.local` matches `web.default.svc.cluster.local` and `api-*.example.com`
+/// matches `api-eu1.example.com`. As with [`host`] the pattern may include a port.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::host_glob("*.svc.cluster.local");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `pattern` is an IPv6 literal without brackets.
+pub fn host_glob(pattern: &str) -> HostGlob {
+    let (pattern, port) = split_port(pattern);
+    HostGlob {
+        pattern: pattern.to_owned(),
+        port,
+    }
+}
+
+impl Condition for HostGlob {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.url()
+            .host_str()
+            .is_some_and(|host| glob_match(self.pattern.as_bytes(), host.as_bytes(), true))
+            && port_matches(req, self.port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    fn matches(condition: &impl Condition, url: &str) -> bool {
+        condition.evaluate(&request(http::Method::GET, url), &Extensions::new())
+    }
+
+    #[test]
+    fn test_host() {
+        let condition = host("API.oxide.computer");
+
+        assert!(matches(&condition, "https://api.oxide.computer/v1"));
+        assert!(matches(&condition, "https://Api.Oxide.Computer:8443/v1"));
+        assert!(!matches(&condition, "https://oxide.computer"));
+        assert!(!matches(&condition, "https://api.oxide.computer.evil.com"));
+    }
+
+    #[test]
+    fn test_host_trailing_dot() {
+        let condition = host("api.oxide.computer");
+        assert!(matches(&condition, "https://api.oxide.computer./v1"));
+
+        let condition = host("api.oxide.computer.:443");
+        assert!(matches(&condition, "https://api.oxide.computer/v1"));
+        assert!(matches(&condition, "https://api.oxide.computer./v1"));
+    }
+
+    #[test]
+    #[should_panic(expected = "must be bracketed")]
+    fn test_host_unbracketed_ipv6() {
+        host("::1");
+    }
+
+    #[test]
+    fn test_host_with_port() {
+        let condition = host("api.oxide.computer:443");
+
+        assert!(matches(&condition, "https://api.oxide.computer/v1"));
+        assert!(matches(&condition, "http://api.oxide.computer:443/v1"));
+        assert!(!matches(&condition, "http://api.oxide.computer/v1"));
+
+        let condition = host("[::1]:8080");
+        assert!(matches(&condition, "http://[::1]:8080"));
+        assert!(!matches(&condition, "http://[::1]"));
+
+        let condition = host("[::1]");
+        assert!(matches(&condition, "http://[::1]:8080"));
+    }
+
+    #[test]
+    fn test_domain() {
+        let condition = domain("Example.com");
+
+        assert!(matches(&condition, "https://example.com"));
+        assert!(matches(&condition, "https://example.com./v1"));
+        assert!(matches(&condition, "https://api.EXAMPLE.com"));
+        assert!(matches(&condition, "https://a.b.example.com:8443"));
+        assert!(!matches(&condition, "https://evil-example.com"));
+        assert!(!matches(&condition, "https://example.com.evil.com"));
+        assert!(!matches(&condition, "https://com"));
+
+        let condition = domain(".internal.example.com");
+        assert!(matches(&condition, "https://db.internal.example.com"));
+        assert!(!matches(&condition, "https://internal.example.com"));
+    }
+
+    #[test]
+    fn test_host_glob() {
+        let condition = host_glob("*.svc.cluster.local");
+
+        assert!(matches(&condition, "http://web.default.svc.cluster.local"));
+        assert!(!matches(&condition, "http://svc.cluster.local"));
+
+        let condition = host_glob("API-*.example.com:8443");
+        assert!(matches(&condition, "https://api-eu1.example.com:8443"));
+        assert!(!matches(&condition, "https://api-eu1.example.com"));
+        assert!(!matches(&condition, "https://api.example.com:8443"));
+    }
+}
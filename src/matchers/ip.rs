@@ -0,0 +1,269 @@
+use http::Extensions;
+use reqwest::{Request, Url};
+use std::net::IpAddr;
+#[cfg(any(feature = "dns", test))]
+use std::net::Ipv4Addr;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// Returns the host of `url` if it is an IP address literal
+pub(crate) fn host_ip(url: &Url) -> Option<IpAddr> {
+    let host = url.host_str()?;
+    let host = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    host.parse().ok()
+}
+
+/// Returns true if `addr` is not publicly routable: private, shared, loopback, link-local,
+/// unique or site local, unspecified, reserved, benchmarking or multicast. IPv4 addresses
+/// embedded in IPv6 ones, mapped, compatible, NAT64 or 6to4, are checked as IPv4 addresses.
+#[cfg(any(feature = "dns", test))]
+pub(crate) fn is_internal(addr: IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => {
+            let [a, b, c, _] = addr.octets();
+            addr.is_private()
+                || addr.is_loopback()
+                || addr.is_link_local()
+                || addr.is_multicast()
+                // "This network", 0.0.0.0/8
+                || a == 0
+                // Shared address space used by carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64)
+                // IETF protocol assignments, 192.0.0.0/24
+                || (a == 192 && b == 0 && c == 0)
+                // Benchmarking, 198.18.0.0/15
+                || (a == 198 && (b & 0xfe) == 18)
+                // Reserved, 240.0.0.0/4, including the broadcast address
+                || a >= 240
+        }
+        IpAddr::V6(addr) => {
+            let segments = addr.segments();
+            let embedded = |high: u16, low: u16| {
+                is_internal(IpAddr::V4(Ipv4Addr::from(
+                    (u32::from(high) << 16) | u32::from(low),
+                )))
+            };
+            if let Some(addr) = addr.to_ipv4_mapped() {
+                return is_internal(IpAddr::V4(addr));
+            }
+            match segments {
+                // IPv4-compatible, ::a.b.c.d, which includes the loopback and unspecified
+                // addresses
+                [0, 0, 0, 0, 0, 0, high, low] => addr.is_loopback() || embedded(high, low),
+                // NAT64, 64:ff9b::/96
+                [0x64, 0xff9b, 0, 0, 0, 0, high, low] => embedded(high, low),
+                // 6to4, 2002::/16
+                [0x2002, high, low, ..] => embedded(high, low),
+                [first, ..] => {
+                    addr.is_multicast()
+                        || (first & 0xfe00) == 0xfc00
+                        || (first & 0xffc0) == 0xfe80
+                        // Deprecated site-local, fec0::/10
+                        || (first & 0xffc0) == 0xfec0
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Network {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl Network {
+    fn parse(range: &str) -> Option<Self> {
+        let (addr, prefix_len) = match range.split_once('/') {
+            Some((addr, prefix_len)) => (addr.parse().ok()?, Some(prefix_len.parse().ok()?)),
+            None => (range.parse().ok()?, None),
+        };
+        let max = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        let prefix_len = prefix_len.unwrap_or(max);
+        (prefix_len <= max).then_some(Self { addr, prefix_len })
+    }
+
+    fn contains(&self, addr: IpAddr) -> bool {
+        let (network, addr, bits) = match (self.addr, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                (u32::from(network) as u128, u32::from(addr) as u128, 32)
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => (u128::from(network), u128::from(addr), 128),
+            _ => return false,
+        };
+        // Shifting a u128 by 128 overflows, a zero-length prefix contains everything
+        let host_bits = bits - u32::from(self.prefix_len);
+        host_bits == 128 || (network ^ addr) >> host_bits == 0
+    }
+}
+
+/// A [`Condition`] matching requests to IP addresses within a set of networks. Created by
+/// [`cidr`].
+#[derive(Clone, Debug)]
+pub struct Cidr(Vec<Network>);
+
+impl_condition_ops!(Cidr);
+
+/// Matches requests whose URL host is an IP address literal within any of `ranges`, given in
+/// CIDR notation such as `10.0.0.0/8` or `fd00::/8`. A range without a prefix length matches
+/// that single address. Requests to domain names never match, no DNS resolution is done.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let in_cluster = matchers::cidr(["10.0.0.0/8", "172.16.0.0/12", "fd00::/8"]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a range is not a valid IP address or CIDR range.
+pub fn cidr<'a>(ranges: impl IntoIterator<Item = &'a str>) -> Cidr {
+    Cidr(
+        ranges
+            .into_iter()
+            .map(|range| Network::parse(range).expect("invalid CIDR range"))
+            .collect(),
+    )
+}
+
+impl Condition for Cidr {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        host_ip(req.url()).is_some_and(|addr| self.0.iter().any(|network| network.contains(addr)))
+    }
+}
+
+/// A [`Condition`] matching requests to the local machine. Created by [`loopback`].
+#[derive(Clone, Copy, Debug)]
+pub struct Loopback;
+
+impl_condition_ops!(Loopback);
+
+/// Matches requests to a loopback address: `localhost` and its subdomains, any address in
+/// `127.0.0.0/8`, and `::1`, including IPv4 loopback addresses mapped into IPv6. Useful to
+/// disable retries or tracing against local development servers and test fixtures.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let remote = !matchers::loopback();
+/// ```
+pub fn loopback() -> Loopback {
+    Loopback
+}
+
+impl Condition for Loopback {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        if let Some(addr) = host_ip(req.url()) {
+            return match addr {
+                IpAddr::V4(addr) => addr.is_loopback(),
+                IpAddr::V6(addr) => {
+                    addr.is_loopback() || addr.to_ipv4_mapped().is_some_and(|v4| v4.is_loopback())
+                }
+            };
+        }
+
+        let host = req.url().host_str().unwrap_or_default();
+        let host = host.strip_suffix('.').unwrap_or(host);
+        host == "localhost" || host.ends_with(".localhost")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    fn matches(condition: &impl Condition, url: &str) -> bool {
+        condition.evaluate(&request(http::Method::GET, url), &Extensions::new())
+    }
+
+    #[test]
+    fn test_cidr() {
+        let condition = cidr(["10.0.0.0/8", "192.168.1.1", "fd00::/8"]);
+
+        assert!(matches(&condition, "http://10.1.2.3:8080/"));
+        assert!(!matches(&condition, "http://11.0.0.1/"));
+        assert!(matches(&condition, "http://192.168.1.1/"));
+        assert!(!matches(&condition, "http://192.168.1.2/"));
+        assert!(matches(&condition, "http://[fd12::1]/"));
+        assert!(!matches(&condition, "http://[fe80::1]/"));
+        assert!(!matches(&condition, "http://10.example.com/"));
+
+        assert!(matches(&cidr(["0.0.0.0/0"]), "http://1.2.3.4/"));
+        assert!(!matches(&cidr(["0.0.0.0/0"]), "http://[::1]/"));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid CIDR range")]
+    fn test_invalid_cidr() {
+        cidr(["10.0.0.0/33"]);
+    }
+
+    #[test]
+    fn test_loopback() {
+        assert!(matches(&loopback(), "http://localhost:8080/"));
+        assert!(matches(&loopback(), "http://LocalHost./"));
+        assert!(matches(&loopback(), "http://api.localhost/"));
+        assert!(matches(&loopback(), "http://127.0.0.1/"));
+        assert!(matches(&loopback(), "http://127.1.2.3/"));
+        assert!(matches(&loopback(), "http://[::1]:3000/"));
+        assert!(matches(&loopback(), "http://[::ffff:127.0.0.1]/"));
+        assert!(!matches(&loopback(), "http://10.0.0.1/"));
+        assert!(!matches(&loopback(), "http://notlocalhost/"));
+        assert!(!matches(&loopback(), "http://localhost.example.com/"));
+    }
+
+    #[test]
+    fn test_is_internal() {
+        for internal in [
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.0.1",
+            "100.64.0.1",
+            "127.0.0.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+            "0.1.2.3",
+            "192.0.0.8",
+            "198.18.0.1",
+            "198.19.255.255",
+            "240.0.0.1",
+            "255.255.255.255",
+            "224.0.0.1",
+            "::",
+            "::10.0.0.1",
+            "64:ff9b::7f00:1",
+            "64:ff9b::a9fe:a9fe",
+            "2002:a00:1::1",
+            "2002:c0a8:101::",
+            "fec0::1",
+            "ff02::1",
+        ] {
+            assert!(is_internal(internal.parse().unwrap()), "{internal}");
+        }
+        for public in [
+            "8.8.8.8",
+            "100.128.0.1",
+            "2001:4860:4860::8888",
+            "::ffff:8.8.8.8",
+            "198.20.0.1",
+            "192.0.1.1",
+            "::8.8.8.8",
+            "64:ff9b::808:808",
+            "2002:808:808::1",
+        ] {
+            assert!(!is_internal(public.parse().unwrap()), "{public}");
+        }
+    }
+}
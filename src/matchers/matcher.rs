@@ -0,0 +1,165 @@
+use http::header::HeaderName;
+use http::Extensions;
+use reqwest::Request;
+use std::fmt::Debug;
+
+use super::{
+    content_type, domain, has_header, header_eq, host, path_prefix, query_param, route,
+    ContentType, Domain, HasHeader, HeaderEq, Host, Method, Methods, PathPrefix, QueryParam, Route,
+    Scheme,
+};
+use crate::condition::{constant, impl_condition_ops, And, Constant};
+use crate::Condition;
+
+/// A [`Condition`] composed fluently from the built-in matchers, true when every matcher added
+/// to it is. An empty `Matcher` matches every request.
+///
+/// Each method wraps the matcher built so far in an [`And`], so the composed condition is a
+/// single concrete type evaluated without boxing or dynamic dispatch. Box it as a
+/// `Box<dyn Condition>` where its type needs to be named.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers::Matcher;
+///
+/// let condition = Matcher::new()
+///     .host("api.example.com")
+///     .path_prefix("/v1")
+///     .method(http::Method::GET);
+/// ```
+#[derive(Clone)]
+pub struct Matcher<C = Constant>(C);
+
+impl_condition_ops!(Matcher<C>);
+
+impl Matcher {
+    /// Creates a matcher that matches every request
+    pub fn new() -> Self {
+        Self(constant(true))
+    }
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> Matcher<C>
+where
+    C: Condition,
+{
+    /// Also requires `condition` to match, for conditions without a dedicated method
+    pub fn when<D>(self, condition: D) -> Matcher<And<C, D>>
+    where
+        D: Condition,
+    {
+        Matcher(self.0.and(condition))
+    }
+
+    /// Also requires the host to match, see [`host`](super::host)
+    pub fn host(self, pattern: &str) -> Matcher<And<C, Host>> {
+        self.when(host(pattern))
+    }
+
+    /// Also requires the host to be within a domain, see [`domain`](super::domain)
+    pub fn domain(self, domain_name: &str) -> Matcher<And<C, Domain>> {
+        self.when(domain(domain_name))
+    }
+
+    /// Also requires the scheme to match, see [`scheme`](super::scheme)
+    pub fn scheme(self, scheme: Scheme) -> Matcher<And<C, Scheme>> {
+        self.when(scheme)
+    }
+
+    /// Also requires the path to start with a prefix, see [`path_prefix`](super::path_prefix)
+    pub fn path_prefix(self, prefix: &str) -> Matcher<And<C, PathPrefix>> {
+        self.when(path_prefix(prefix))
+    }
+
+    /// Also requires the path to fit a route template, see [`route`](super::route)
+    pub fn route(self, template: &str) -> Matcher<And<C, Route>> {
+        self.when(route(template))
+    }
+
+    /// Also requires the method to be `method`
+    pub fn method(self, method: http::Method) -> Matcher<And<C, Method>> {
+        self.when(Method::is(method))
+    }
+
+    /// Also requires the method to be one of `methods`
+    pub fn methods(
+        self,
+        methods: impl IntoIterator<Item = http::Method>,
+    ) -> Matcher<And<C, Methods>> {
+        self.when(Methods::any_of(methods))
+    }
+
+    /// Also requires a header to be present, see [`has_header`](super::has_header)
+    pub fn header<N>(self, name: N) -> Matcher<And<C, HasHeader>>
+    where
+        N: TryInto<HeaderName>,
+        N::Error: Debug,
+    {
+        self.when(has_header(name))
+    }
+
+    /// Also requires a header to have a value, see [`header_eq`](super::header_eq)
+    pub fn header_eq<N>(self, name: N, value: impl Into<String>) -> Matcher<And<C, HeaderEq>>
+    where
+        N: TryInto<HeaderName>,
+        N::Error: Debug,
+    {
+        self.when(header_eq(name, value))
+    }
+
+    /// Also requires a query parameter to be present, see [`query_param`](super::query_param)
+    pub fn query_param(self, name: impl Into<String>) -> Matcher<And<C, QueryParam>> {
+        self.when(query_param(name))
+    }
+
+    /// Also requires the body to have a media type, see [`content_type`](super::content_type)
+    pub fn content_type(self, media_type: &str) -> Matcher<And<C, ContentType>> {
+        self.when(content_type(media_type))
+    }
+}
+
+impl<C> Condition for Matcher<C>
+where
+    C: Condition,
+{
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        self.0.evaluate(req, extensions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    #[test]
+    fn test_matcher() {
+        let extensions = Extensions::new();
+        let condition = Matcher::new()
+            .host("api.example.com")
+            .path_prefix("/v1")
+            .method(http::Method::GET)
+            .when(!has_header("x-skip"));
+
+        let req = request(http::Method::GET, "https://api.example.com/v1/projects");
+        assert!(condition.evaluate(&req, &extensions));
+
+        let req = request(http::Method::POST, "https://api.example.com/v1/projects");
+        assert!(!condition.evaluate(&req, &extensions));
+
+        let req = request(http::Method::GET, "https://api.example.com/v2/projects");
+        assert!(!condition.evaluate(&req, &extensions));
+
+        let mut req = request(http::Method::GET, "https://api.example.com/v1/projects");
+        req.headers_mut()
+            .insert("x-skip", http::HeaderValue::from_static("1"));
+        assert!(!condition.evaluate(&req, &extensions));
+
+        assert!(Matcher::new().evaluate(&req, &extensions));
+    }
+}
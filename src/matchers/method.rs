@@ -0,0 +1,126 @@
+use http::Extensions;
+use reqwest::Request;
+use std::borrow::Cow;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] matching requests with a given HTTP method
+#[derive(Clone, Debug)]
+pub struct Method(http::Method);
+
+impl_condition_ops!(Method);
+
+impl Method {
+    /// Matches requests whose method is `method`
+    pub fn is(method: http::Method) -> Self {
+        Self(method)
+    }
+}
+
+impl Condition for Method {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        *req.method() == self.0
+    }
+}
+
+const SAFE: &[http::Method] = &[
+    http::Method::GET,
+    http::Method::HEAD,
+    http::Method::OPTIONS,
+    http::Method::TRACE,
+];
+
+const IDEMPOTENT: &[http::Method] = &[
+    http::Method::GET,
+    http::Method::HEAD,
+    http::Method::OPTIONS,
+    http::Method::TRACE,
+    http::Method::PUT,
+    http::Method::DELETE,
+];
+
+const MUTATING: &[http::Method] = &[
+    http::Method::POST,
+    http::Method::PUT,
+    http::Method::PATCH,
+    http::Method::DELETE,
+];
+
+/// A [`Condition`] matching requests whose HTTP method is one of a set of methods
+#[derive(Clone, Debug)]
+pub struct Methods(Cow<'static, [http::Method]>);
+
+impl_condition_ops!(Methods);
+
+impl Methods {
+    /// Matches requests whose method is any of `methods`
+    pub fn any_of(methods: impl IntoIterator<Item = http::Method>) -> Self {
+        Self(methods.into_iter().collect())
+    }
+
+    /// Matches requests with a safe method as defined by
+    /// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-9.2.1): `GET`, `HEAD`,
+    /// `OPTIONS` and `TRACE`
+    pub fn safe() -> Self {
+        Self(Cow::Borrowed(SAFE))
+    }
+
+    /// Matches requests with an idempotent method as defined by
+    /// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-9.2.2): the safe methods,
+    /// `PUT` and `DELETE`
+    pub fn idempotent() -> Self {
+        Self(Cow::Borrowed(IDEMPOTENT))
+    }
+
+    /// Matches requests with a method that modifies state on the server: `POST`, `PUT`,
+    /// `PATCH` and `DELETE`, for write-path middleware such as idempotency keys or audit logs
+    ///
+    /// ```
+    /// use reqwest_conditional_middleware::matchers::Methods;
+    ///
+    /// let writes = Methods::mutating();
+    /// ```
+    pub fn mutating() -> Self {
+        Self(Cow::Borrowed(MUTATING))
+    }
+}
+
+impl Condition for Methods {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        self.0.contains(req.method())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    #[test]
+    fn test_method() {
+        let extensions = Extensions::new();
+        let condition = Method::is(http::Method::GET);
+
+        assert!(condition.evaluate(&request(http::Method::GET, "http://a"), &extensions));
+        assert!(!condition.evaluate(&request(http::Method::POST, "http://a"), &extensions));
+    }
+
+    #[test]
+    fn test_methods() {
+        let extensions = Extensions::new();
+        let req = |method| request(method, "http://a");
+
+        assert!(Methods::safe().evaluate(&req(http::Method::HEAD), &extensions));
+        assert!(!Methods::safe().evaluate(&req(http::Method::PUT), &extensions));
+        assert!(Methods::idempotent().evaluate(&req(http::Method::PUT), &extensions));
+        assert!(!Methods::idempotent().evaluate(&req(http::Method::POST), &extensions));
+        assert!(Methods::mutating().evaluate(&req(http::Method::PATCH), &extensions));
+        assert!(Methods::mutating().evaluate(&req(http::Method::DELETE), &extensions));
+        assert!(!Methods::mutating().evaluate(&req(http::Method::OPTIONS), &extensions));
+
+        let condition = Methods::any_of([http::Method::POST, http::Method::PATCH]);
+        assert!(condition.evaluate(&req(http::Method::PATCH), &extensions));
+        assert!(!condition.evaluate(&req(http::Method::GET), &extensions));
+    }
+}
@@ -0,0 +1,110 @@
+//! Built-in [`Condition`][crate::Condition]s matching common properties of a request, so that
+//! the most common gating does not need hand-written closures. Every matcher supports the
+//! `&`, `|` and `!` operators, and [`not`] negates any condition, closures included.
+//!
+//! Matchers using regular expressions are available with the `regex` feature, and
+//! `MatcherConfig` for describing matchers in configuration files with the `serde` feature.
+//! The `config-file` feature adds `watch_config_file`, which reloads such a file as it changes.
+//! The `cel` feature adds `cel`, for conditions written in the Common Expression Language, and
+//! the `opentelemetry` feature adds matchers on OpenTelemetry baggage and sampling. Matching
+//! GraphQL operations requires the `graphql` feature, and resolving destinations for SSRF
+//! guards the `dns` feature.
+//!
+//! ```
+//! use reqwest_conditional_middleware::matchers::{self, Method, Methods};
+//!
+//! let condition = Method::is(http::Method::POST) | Methods::safe();
+//! let not_health_check = matchers::not(matchers::path_prefix("/health"));
+//! ```
+
+#[cfg(feature = "opentelemetry")]
+mod baggage;
+mod body;
+#[cfg(feature = "cel")]
+mod cel;
+#[cfg(feature = "serde")]
+mod config;
+#[cfg(feature = "config-file")]
+mod config_file;
+mod deadline;
+#[cfg(feature = "dns")]
+mod dns;
+mod expr;
+mod extension;
+mod glob;
+#[cfg(feature = "graphql")]
+mod graphql;
+mod header;
+mod host;
+mod ip;
+mod matcher;
+mod method;
+mod path;
+mod query;
+#[cfg(feature = "regex")]
+mod regex;
+mod retry;
+mod set;
+mod tenant;
+mod timeout;
+mod trace;
+mod url;
+mod version;
+
+pub use crate::condition::not;
+#[cfg(feature = "opentelemetry")]
+pub use baggage::{baggage_eq, has_baggage, Baggage};
+pub use body::{
+    body_size_at_least, body_size_at_most, has_body, has_buffered_body, has_streaming_body,
+    BodySize, BufferedBody, HasBody, StreamingBody,
+};
+#[cfg(feature = "cel")]
+pub use cel::{cel, Cel, CelError};
+#[cfg(feature = "serde")]
+pub use config::{HeaderConfig, MatcherConfig, MatcherConfigError, QueryConfig};
+#[cfg(feature = "config-file")]
+pub use config_file::{watch_config_file, ConfigFile, ConfigFileError};
+pub use deadline::{budget_at_least, Deadline, RemainingBudget, SetDeadline};
+#[cfg(feature = "dns")]
+pub use dns::{resolves_to_internal, InternalDestination};
+pub use expr::{expression, Expression, ExpressionError};
+pub use extension::{has_extension, HasExtension};
+#[cfg(feature = "graphql")]
+pub use graphql::{graphql_operation, GraphqlOperation, OperationType};
+pub(crate) use header::header_name;
+pub use header::{
+    accepts, authorization_scheme, content_type, has_authorization, has_cookie, has_header,
+    has_idempotency_key, header_eq, is_event_stream, is_grpc, is_multipart, is_range_request,
+    is_revalidation, is_upgrade, user_agent_contains, user_agent_glob, Accepts, Authorization,
+    ContentType, EventStream, Grpc, HasCookie, HasHeader, HeaderEq, Revalidation, Upgrade,
+    UserAgent,
+};
+pub use host::{domain, host, host_glob, Domain, Host, HostGlob};
+pub use ip::{cidr, loopback, Cidr, Loopback};
+pub use matcher::Matcher;
+pub use method::{Method, Methods};
+pub use path::{path_prefix, route, PathPrefix, Route};
+pub use query::{query_param, query_param_eq, QueryParam};
+#[cfg(feature = "regex")]
+pub use regex::{
+    header_regex, host_regex, path_regex, url_regex, HeaderRegex, HostRegex, PathRegex, UrlRegex,
+};
+pub use retry::{is_first_attempt, is_retry, Attempt, CountAttempts, RetryAttempt};
+pub use set::{allowlist, denylist, UrlSet};
+pub use tenant::{tenant_policy, TenantPolicy};
+pub use timeout::{has_timeout, timeout_below, RequestTimeout};
+pub use trace::{is_sampled, span_enabled, Sampled, SpanEnabled, TraceSampled};
+pub use url::{
+    base_url, has_userinfo, port, scheme, url_glob, url_longer_than, BaseUrl, Port, Scheme,
+    UrlGlob, UrlLength, Userinfo,
+};
+pub use version::{version, Version};
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use reqwest::Request;
+
+    pub(crate) fn request(method: http::Method, url: &str) -> Request {
+        Request::new(method, url.parse().unwrap())
+    }
+}
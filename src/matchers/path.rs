@@ -0,0 +1,195 @@
+use http::Extensions;
+use percent_encoding::percent_decode_str;
+use reqwest::Request;
+use std::borrow::Cow;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// Percent-decodes a path, only allocating when it contains escapes
+pub(crate) fn decode_path(path: &str) -> Cow<'_, [u8]> {
+    percent_decode_str(path).into()
+}
+
+/// A [`Condition`] matching requests whose path starts with a prefix. Created by
+/// [`path_prefix`].
+#[derive(Clone, Debug)]
+pub struct PathPrefix {
+    prefix: Vec<u8>,
+}
+
+impl_condition_ops!(PathPrefix);
+
+/// Matches requests whose URL path starts with `prefix` on a segment boundary:
+/// `path_prefix("/v1/admin")` matches `/v1/admin`, `/v1/admin/` and `/v1/admin/users` but not
+/// `/v1/administrators`. A trailing slash on the prefix is ignored. Both the prefix and the
+/// request path are percent-decoded before being compared.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::path_prefix("/v1/admin");
+/// ```
+pub fn path_prefix(prefix: &str) -> PathPrefix {
+    let mut prefix = decode_path(prefix).into_owned();
+    while prefix.last() == Some(&b'/') {
+        prefix.pop();
+    }
+    PathPrefix { prefix }
+}
+
+impl PathPrefix {
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        // Decode lazily rather than through `decode_path` so that escaped paths do not allocate
+        let mut path = percent_decode_str(path);
+        self.prefix.iter().all(|byte| path.next() == Some(*byte))
+            && matches!(path.next(), None | Some(b'/'))
+    }
+}
+
+impl Condition for PathPrefix {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        self.matches(req.url().path())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Literal(Vec<u8>),
+    Param,
+    CatchAll,
+}
+
+/// A [`Condition`] matching requests whose path fits a route template. Created by [`route`].
+#[derive(Clone, Debug)]
+pub struct Route {
+    segments: Vec<Segment>,
+}
+
+impl_condition_ops!(Route);
+
+/// Matches requests whose URL path fits `template` segment by segment. A `{name}` segment
+/// matches any single non-empty segment and a final `{*name}` segment matches the rest of the
+/// path, including nothing. Other segments must match exactly after percent-decoding, and a
+/// trailing slash on either side is ignored.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let disks = matchers::route("/v1/instances/{id}/disks");
+/// let assets = matchers::route("/static/{*path}");
+/// ```
+///
+/// # Panics
+///
+/// Panics if a `{*name}` segment is not the last one.
+pub fn route(template: &str) -> Route {
+    try_route(template).unwrap_or_else(|message| panic!("{message}"))
+}
+
+/// Parses a route template, returning an error message if it is invalid
+pub(crate) fn try_route(template: &str) -> Result<Route, String> {
+    let segments: Vec<_> = split_segments(template)
+        .map(|segment| {
+            if segment.starts_with("{*") && segment.ends_with('}') {
+                Segment::CatchAll
+            } else if segment.starts_with('{') && segment.ends_with('}') {
+                Segment::Param
+            } else {
+                Segment::Literal(decode_path(segment).into_owned())
+            }
+        })
+        .collect();
+    match segments.iter().position(|s| *s == Segment::CatchAll) {
+        Some(position) if position != segments.len() - 1 => Err(format!(
+            "catch-all segment must be last in route template {template:?}"
+        )),
+        _ => Ok(Route { segments }),
+    }
+}
+
+fn split_segments(path: &str) -> impl Iterator<Item = &str> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let path = path.strip_suffix('/').unwrap_or(path);
+    path.split('/').filter(move |_| !path.is_empty())
+}
+
+impl Condition for Route {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        let mut path = split_segments(req.url().path());
+        for segment in &self.segments {
+            match segment {
+                Segment::CatchAll => return true,
+                Segment::Param => match path.next() {
+                    Some(value) if !value.is_empty() => {}
+                    _ => return false,
+                },
+                Segment::Literal(literal) => match path.next() {
+                    Some(value) if percent_decode_str(value).eq(literal.iter().copied()) => {}
+                    _ => return false,
+                },
+            }
+        }
+        path.next().is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    fn matches(condition: &impl Condition, url: &str) -> bool {
+        condition.evaluate(&request(http::Method::GET, url), &Extensions::new())
+    }
+
+    #[test]
+    fn test_path_prefix() {
+        let condition = path_prefix("/v1/admin/");
+
+        assert!(matches(&condition, "http://a/v1/admin"));
+        assert!(matches(&condition, "http://a/v1/admin/"));
+        assert!(matches(&condition, "http://a/v1/admin/users?page=2"));
+        assert!(matches(&condition, "http://a/v1/%61dmin/users"));
+        assert!(!matches(&condition, "http://a/v1/administrators"));
+        assert!(!matches(&condition, "http://a/v1"));
+
+        let condition = path_prefix("/v1/with space");
+        assert!(matches(&condition, "http://a/v1/with%20space/x"));
+
+        assert!(matches(&path_prefix("/"), "http://a/anything"));
+    }
+
+    #[test]
+    fn test_route() {
+        let condition = route("/v1/instances/{id}/disks");
+
+        assert!(matches(&condition, "http://a/v1/instances/web-1/disks"));
+        assert!(matches(
+            &condition,
+            "http://a/v1/instances/web-1/disks/?limit=5"
+        ));
+        assert!(matches(&condition, "http://a/v1/%69nstances/web-1/disks"));
+        assert!(!matches(&condition, "http://a/v1/instances//disks"));
+        assert!(!matches(&condition, "http://a/v1/instances/web-1"));
+        assert!(!matches(
+            &condition,
+            "http://a/v1/instances/web-1/disks/d-1"
+        ));
+        assert!(!matches(&condition, "http://a/v1/instances/a%2Fb/c/disks"));
+
+        let condition = route("/static/{*path}");
+        assert!(matches(&condition, "http://a/static"));
+        assert!(matches(&condition, "http://a/static/css/main.css"));
+        assert!(!matches(&condition, "http://a/assets/main.css"));
+
+        assert!(matches(&route("/"), "http://a/"));
+        assert!(!matches(&route("/"), "http://a/x"));
+    }
+
+    #[test]
+    #[should_panic(expected = "catch-all segment must be last")]
+    fn test_route_catch_all_not_last() {
+        route("/static/{*path}/x");
+    }
+}
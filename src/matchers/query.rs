@@ -0,0 +1,81 @@
+use http::Extensions;
+use reqwest::Request;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] matching requests carrying a query parameter. Created by [`query_param`]
+/// and [`query_param_eq`].
+#[derive(Clone, Debug)]
+pub struct QueryParam {
+    name: String,
+    value: Option<String>,
+}
+
+impl_condition_ops!(QueryParam);
+
+/// Matches requests whose query string contains the parameter `name`, with any value.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::query_param("dry_run");
+/// ```
+pub fn query_param(name: impl Into<String>) -> QueryParam {
+    QueryParam {
+        name: name.into(),
+        value: None,
+    }
+}
+
+/// Matches requests whose query string contains the parameter `name` with the given value. When
+/// the parameter is repeated any of its values may match.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::query_param_eq("debug", "true");
+/// ```
+pub fn query_param_eq(name: impl Into<String>, value: impl Into<String>) -> QueryParam {
+    QueryParam {
+        name: name.into(),
+        value: Some(value.into()),
+    }
+}
+
+impl Condition for QueryParam {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        // Names and values are compared after form-urlencoded decoding
+        req.url().query_pairs().any(|(name, value)| {
+            name == self.name.as_str()
+                && self
+                    .value
+                    .as_deref()
+                    .is_none_or(|expected| value == expected)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    fn matches(condition: &impl Condition, url: &str) -> bool {
+        condition.evaluate(&request(http::Method::GET, url), &Extensions::new())
+    }
+
+    #[test]
+    fn test_query_param() {
+        let condition = query_param("dry_run");
+        assert!(matches(&condition, "http://a/?dry_run"));
+        assert!(matches(&condition, "http://a/?page=2&dry%5Frun=0"));
+        assert!(!matches(&condition, "http://a/?dry_run_all=1"));
+        assert!(!matches(&condition, "http://a/dry_run"));
+
+        let condition = query_param_eq("name", "a b/c");
+        assert!(matches(&condition, "http://a/?name=a+b%2Fc"));
+        assert!(matches(&condition, "http://a/?name=x&name=a%20b/c"));
+        assert!(!matches(&condition, "http://a/?name=a+b"));
+    }
+}
@@ -0,0 +1,167 @@
+use http::header::HeaderName;
+use http::Extensions;
+use regex::Regex;
+use reqwest::Request;
+use std::fmt::Debug;
+
+use crate::condition::impl_condition_ops;
+use crate::matchers::header::header_name;
+use crate::matchers::path::decode_path;
+use crate::Condition;
+
+/// A [`Condition`] matching requests whose host matches a regular expression. Created by
+/// [`host_regex`].
+#[derive(Clone, Debug)]
+pub struct HostRegex(Regex);
+
+impl_condition_ops!(HostRegex);
+
+/// Matches requests whose URL host matches `regex`. Hosts are lowercase for `http` and
+/// `https` URLs. The expression is not anchored, use `^` and `$` to match the whole host.
+///
+/// ```
+/// use regex::Regex;
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::host_regex(Regex::new(r"^api-[a-z]{2}\d\.example\.com$").unwrap());
+/// ```
+pub fn host_regex(regex: Regex) -> HostRegex {
+    HostRegex(regex)
+}
+
+impl Condition for HostRegex {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.url()
+            .host_str()
+            .is_some_and(|host| self.0.is_match(host))
+    }
+}
+
+/// A [`Condition`] matching requests whose full URL matches a regular expression. Created by
+/// [`url_regex`].
+#[derive(Clone, Debug)]
+pub struct UrlRegex(Regex);
+
+impl_condition_ops!(UrlRegex);
+
+/// Matches requests whose serialized URL matches `regex`. The expression is not anchored, use
+/// `^` and `$` to match the whole URL.
+pub fn url_regex(regex: Regex) -> UrlRegex {
+    UrlRegex(regex)
+}
+
+impl Condition for UrlRegex {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        self.0.is_match(req.url().as_str())
+    }
+}
+
+/// A [`Condition`] matching requests whose path matches a regular expression. Created by
+/// [`path_regex`].
+#[derive(Clone, Debug)]
+pub struct PathRegex(Regex);
+
+impl_condition_ops!(PathRegex);
+
+/// Matches requests whose URL path matches `regex`. Like [`path_prefix`](super::path_prefix),
+/// the path is percent-decoded first and does not include the query string. The expression is
+/// not anchored, use `^` and `$` to match the whole path.
+///
+/// ```
+/// use regex::Regex;
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::path_regex(Regex::new(r"^/v\d+/projects/[^/]+/admin(/|$)").unwrap());
+/// ```
+pub fn path_regex(regex: Regex) -> PathRegex {
+    PathRegex(regex)
+}
+
+impl Condition for PathRegex {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        let path = decode_path(req.url().path());
+        self.0.is_match(&String::from_utf8_lossy(&path))
+    }
+}
+
+/// A [`Condition`] matching requests with a header value matching a regular expression.
+/// Created by [`header_regex`].
+#[derive(Clone, Debug)]
+pub struct HeaderRegex {
+    name: HeaderName,
+    regex: Regex,
+}
+
+impl_condition_ops!(HeaderRegex);
+
+/// Matches requests with a `name` header whose value matches `regex`. When the header is sent
+/// more than once any of its values may match, and values that are not valid UTF-8 never match.
+/// The expression is not anchored, use `^` and `$` to match the whole value.
+///
+/// ```
+/// use regex::Regex;
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::header_regex("x-environment", Regex::new(r"^staging-\d+$").unwrap());
+/// ```
+///
+/// # Panics
+///
+/// Panics if `name` is not a valid header name.
+pub fn header_regex<N>(name: N, regex: Regex) -> HeaderRegex
+where
+    N: TryInto<HeaderName>,
+    N::Error: Debug,
+{
+    HeaderRegex {
+        name: header_name(name),
+        regex,
+    }
+}
+
+impl Condition for HeaderRegex {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.headers()
+            .get_all(&self.name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(|value| self.regex.is_match(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::header::tests::with_headers;
+    use crate::matchers::tests::request;
+
+    #[test]
+    fn test_regex() {
+        let extensions = Extensions::new();
+        let req = request(
+            http::Method::GET,
+            "https://api-eu1.example.com/v1/users?page=2",
+        );
+
+        assert!(host_regex(Regex::new(r"^api-[a-z]{2}\d\.").unwrap()).evaluate(&req, &extensions));
+        assert!(!host_regex(Regex::new(r"^api\.").unwrap()).evaluate(&req, &extensions));
+        assert!(url_regex(Regex::new(r"/v\d+/users\?").unwrap()).evaluate(&req, &extensions));
+        assert!(!url_regex(Regex::new(r"^http://").unwrap()).evaluate(&req, &extensions));
+        assert!(path_regex(Regex::new(r"^/v\d+/users$").unwrap()).evaluate(&req, &extensions));
+        assert!(!path_regex(Regex::new(r"page").unwrap()).evaluate(&req, &extensions));
+
+        let req = request(http::Method::GET, "https://a/v1/with%20space");
+        assert!(path_regex(Regex::new(r"with space$").unwrap()).evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_header_regex() {
+        let extensions = Extensions::new();
+        let req = with_headers(&[("x-environment", "prod"), ("x-environment", "staging-2")]);
+
+        let condition = header_regex("x-environment", Regex::new(r"^staging-\d+$").unwrap());
+        assert!(condition.evaluate(&req, &extensions));
+        let condition = header_regex("x-environment", Regex::new(r"^dev").unwrap());
+        assert!(!condition.evaluate(&req, &extensions));
+    }
+}
@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// The attempt number of a request, starting at 1, recorded in the request extensions by
+/// [`CountAttempts`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Attempt(pub u32);
+
+/// A middleware counting the attempts made to send a request, for [`is_first_attempt`] and
+/// [`is_retry`].
+///
+/// `reqwest-retry` passes the same extensions to every attempt but does not record which
+/// attempt is being made, so install this middleware right after the retry middleware. Each
+/// time a request passes through it the [`Attempt`] in the extensions is incremented.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CountAttempts;
+
+#[async_trait]
+impl Middleware for CountAttempts {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let attempt = extensions.get::<Attempt>().map_or(1, |Attempt(n)| n + 1);
+        extensions.insert(Attempt(attempt));
+        next.run(req, extensions).await
+    }
+}
+
+/// A [`Condition`] matching requests by whether they are being retried. Created by
+/// [`is_first_attempt`] and [`is_retry`].
+#[derive(Clone, Copy, Debug)]
+pub struct RetryAttempt {
+    retry: bool,
+}
+
+impl_condition_ops!(RetryAttempt);
+
+/// Matches the first attempt at sending a request, according to the [`Attempt`] recorded by
+/// [`CountAttempts`]. Without an `Attempt` the request is taken to be a first attempt.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::is_first_attempt();
+/// ```
+pub fn is_first_attempt() -> RetryAttempt {
+    RetryAttempt { retry: false }
+}
+
+/// Matches every attempt at sending a request but the first, according to the [`Attempt`]
+/// recorded by [`CountAttempts`].
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::is_retry();
+/// ```
+pub fn is_retry() -> RetryAttempt {
+    RetryAttempt { retry: true }
+}
+
+impl Condition for RetryAttempt {
+    fn evaluate(&self, _req: &Request, extensions: &Extensions) -> bool {
+        let attempt = extensions.get::<Attempt>().map_or(1, |Attempt(n)| *n);
+        (attempt > 1) == self.retry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{CheckMiddleware, End};
+    use crate::ConditionalMiddleware;
+
+    /// Sends every request twice, like a retry middleware would on a transient failure
+    struct Twice;
+
+    #[async_trait]
+    impl Middleware for Twice {
+        async fn handle(
+            &self,
+            req: Request,
+            extensions: &mut Extensions,
+            next: Next<'_>,
+        ) -> Result<Response> {
+            next.clone()
+                .run(req.try_clone().unwrap(), extensions)
+                .await?;
+            next.run(req, extensions).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_attempt() {
+        let first = CheckMiddleware::new();
+        let ran_first = first.checker();
+        let retry = CheckMiddleware::new();
+        let ran_retry = retry.checker();
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(Twice)
+                .with(CountAttempts)
+                .with(ConditionalMiddleware::new(first, is_first_attempt()))
+                .with(ConditionalMiddleware::new(retry, is_retry()))
+                .with(End)
+                .build();
+        let mut extensions = Extensions::new();
+        client
+            .execute_with_extensions(
+                Request::new(http::Method::GET, "http://localhost".parse().unwrap()),
+                &mut extensions,
+            )
+            .await
+            .unwrap();
+
+        assert!(*ran_first.lock().unwrap());
+        assert!(*ran_retry.lock().unwrap());
+        assert_eq!(extensions.get::<Attempt>(), Some(&Attempt(2)));
+    }
+}
@@ -0,0 +1,170 @@
+use http::Extensions;
+use reqwest::{Request, Url};
+use std::collections::HashMap;
+
+use super::host::split_port;
+use super::url::{base_url, BaseUrl};
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] matching requests against a set of hosts and URL prefixes. Created by
+/// [`allowlist`] and [`denylist`].
+#[derive(Clone, Debug)]
+pub struct UrlSet {
+    /// The ports each host entry matches, `None` matching any port
+    hosts: HashMap<String, Vec<Option<u16>>>,
+    prefixes: HashMap<String, Vec<BaseUrl>>,
+    deny: bool,
+}
+
+impl_condition_ops!(UrlSet);
+
+impl UrlSet {
+    fn new<'a>(entries: impl IntoIterator<Item = &'a str>, deny: bool) -> Self {
+        let mut hosts: HashMap<_, Vec<_>> = HashMap::new();
+        let mut prefixes: HashMap<_, Vec<_>> = HashMap::new();
+        for entry in entries {
+            if !entry.contains("://") {
+                let (host, port) = split_port(entry);
+                hosts
+                    .entry(host.trim_end_matches('.').to_ascii_lowercase())
+                    .or_default()
+                    .push(port);
+                continue;
+            }
+
+            let prefix = base_url(entry);
+            prefixes
+                .entry(prefix.host().trim_end_matches('.').to_owned())
+                .or_default()
+                .push(prefix);
+        }
+        Self {
+            hosts,
+            prefixes,
+            deny,
+        }
+    }
+
+    fn contains(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        // Hosts are compared without the trailing dot of fully qualified names
+        let host = host.strip_suffix('.').unwrap_or(host);
+        let port = url.port_or_known_default();
+        if self
+            .hosts
+            .get(host)
+            .is_some_and(|ports| ports.iter().any(|p| p.is_none() || *p == port))
+        {
+            return true;
+        }
+        self.prefixes.get(host).is_some_and(|prefixes| {
+            prefixes
+                .iter()
+                .any(|prefix| prefix.matches_except_host(url))
+        })
+    }
+}
+
+/// Matches requests to any of `entries`. An entry is either a host such as `api.example.com`,
+/// matching any request to that host, optionally with a port (`api.example.com:8443`) that the
+/// request must then also target, or a URL prefix such as `https://example.com/telemetry`,
+/// matching requests under it as with [`base_url`](super::base_url). Hosts are compared
+/// case-insensitively and without the trailing dot of fully qualified names. Lookups are by
+/// host, so large lists stay cheap to evaluate.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let ours = matchers::allowlist(["metrics.oxide.computer", "https://oxide.computer/api/telemetry"]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a URL prefix cannot be parsed as a URL or has no host, or if a host is an IPv6
+/// literal without brackets.
+pub fn allowlist<'a>(entries: impl IntoIterator<Item = &'a str>) -> UrlSet {
+    UrlSet::new(entries, false)
+}
+
+/// Matches requests to anything but `entries`, the inverse of [`allowlist`].
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let not_internal = matchers::denylist(["localhost", "metadata.internal"]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a URL prefix cannot be parsed as a URL or has no host, or if a host is an IPv6
+/// literal without brackets.
+pub fn denylist<'a>(entries: impl IntoIterator<Item = &'a str>) -> UrlSet {
+    UrlSet::new(entries, true)
+}
+
+impl Condition for UrlSet {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        self.contains(req.url()) != self.deny
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    fn matches(condition: &impl Condition, url: &str) -> bool {
+        condition.evaluate(&request(http::Method::GET, url), &Extensions::new())
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let condition = allowlist(["Metrics.Example.com", "https://example.com/api/telemetry/"]);
+
+        assert!(matches(&condition, "http://metrics.example.com:9090/push"));
+        assert!(matches(&condition, "https://example.com/api/telemetry"));
+        assert!(matches(
+            &condition,
+            "https://example.com:443/api/telemetry/v1"
+        ));
+        assert!(!matches(&condition, "http://example.com/api/telemetry"));
+        assert!(!matches(
+            &condition,
+            "https://example.com:8443/api/telemetry"
+        ));
+        assert!(!matches(&condition, "https://example.com/api/telemetryx"));
+        assert!(!matches(
+            &condition,
+            "https://api.example.com/api/telemetry"
+        ));
+        assert!(matches(&condition, "http://metrics.example.com./push"));
+        assert!(matches(&condition, "https://example.com./api/telemetry"));
+    }
+
+    #[test]
+    fn test_allowlist_port() {
+        let condition = allowlist(["api.example.com:8443", "[::1]:8080", "api.example.com."]);
+
+        assert!(matches(&condition, "https://api.example.com:8443/"));
+        assert!(matches(&condition, "https://api.example.com/"));
+        assert!(matches(&condition, "http://[::1]:8080/"));
+        assert!(!matches(&condition, "http://[::1]/"));
+
+        let condition = allowlist(["api.example.com:8443"]);
+        assert!(!matches(&condition, "https://api.example.com/"));
+        assert!(matches(&condition, "https://api.example.com.:8443/"));
+    }
+
+    #[test]
+    fn test_denylist() {
+        let condition = denylist(["localhost"]);
+
+        assert!(!matches(&condition, "http://localhost:8080/"));
+        assert!(!matches(&condition, "http://localhost./"));
+        assert!(!matches(&condition, "http://LOCALHOST.:8080/"));
+        assert!(matches(&condition, "https://example.com/"));
+    }
+}
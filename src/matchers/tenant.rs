@@ -0,0 +1,90 @@
+use http::header::HeaderName;
+use http::Extensions;
+use reqwest::Request;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use super::header::header_name;
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] looking up whether to run in a per-tenant policy map. Created by
+/// [`tenant_policy`].
+#[derive(Clone, Debug)]
+pub struct TenantPolicy {
+    header: HeaderName,
+    policies: HashMap<String, bool>,
+    default: bool,
+}
+
+impl_condition_ops!(TenantPolicy);
+
+/// Looks up the value of the `header` header, such as `x-tenant-id`, in `policies` to decide
+/// whether to run for each request, so that a multi-tenant service can gate middleware per
+/// customer. Requests without the header, or from tenants missing from `policies`, get
+/// `default`. When the header is repeated its first value is used.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+/// use std::collections::HashMap;
+///
+/// let policies = HashMap::from([("acme".to_owned(), true), ("globex".to_owned(), false)]);
+/// let condition = matchers::tenant_policy("x-tenant-id", policies, false);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `header` is not a valid header name.
+pub fn tenant_policy<N>(header: N, policies: HashMap<String, bool>, default: bool) -> TenantPolicy
+where
+    N: TryInto<HeaderName>,
+    N::Error: Debug,
+{
+    TenantPolicy {
+        header: header_name(header),
+        policies,
+        default,
+    }
+}
+
+impl TenantPolicy {
+    /// Sets the policy for `tenant`, replacing any previous one
+    pub fn insert(&mut self, tenant: impl Into<String>, run: bool) {
+        self.policies.insert(tenant.into(), run);
+    }
+}
+
+impl Condition for TenantPolicy {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.headers()
+            .get(&self.header)
+            .and_then(|tenant| tenant.to_str().ok())
+            .and_then(|tenant| self.policies.get(tenant))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::header::tests::with_headers;
+
+    #[test]
+    fn test_tenant_policy() {
+        let extensions = Extensions::new();
+        let policies = HashMap::from([("acme".to_owned(), true), ("globex".to_owned(), false)]);
+        let mut condition = tenant_policy("x-tenant-id", policies, true);
+
+        let acme = with_headers(&[("x-tenant-id", "acme")]);
+        let globex = with_headers(&[("x-tenant-id", "globex")]);
+        let initech = with_headers(&[("x-tenant-id", "initech")]);
+        assert!(condition.evaluate(&acme, &extensions));
+        assert!(!condition.evaluate(&globex, &extensions));
+        assert!(condition.evaluate(&initech, &extensions));
+        assert!(condition.evaluate(&with_headers(&[]), &extensions));
+
+        condition.insert("initech", false);
+        assert!(!condition.evaluate(&initech, &extensions));
+    }
+}
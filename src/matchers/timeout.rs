@@ -0,0 +1,70 @@
+use http::Extensions;
+use reqwest::Request;
+use std::time::Duration;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] matching requests with a per-request timeout. Created by [`has_timeout`] and
+/// [`timeout_below`].
+#[derive(Clone, Copy, Debug)]
+pub struct RequestTimeout {
+    below: Option<Duration>,
+}
+
+impl_condition_ops!(RequestTimeout);
+
+/// Matches requests with a timeout set through
+/// [`RequestBuilder::timeout`](reqwest::RequestBuilder::timeout). A timeout configured on the
+/// client is not visible to middleware and is not taken into account.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::has_timeout();
+/// ```
+pub fn has_timeout() -> RequestTimeout {
+    RequestTimeout { below: None }
+}
+
+/// Matches requests with a per-request timeout strictly shorter than `threshold`, for instance
+/// to skip heavyweight middleware on latency-critical calls.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+/// use std::time::Duration;
+///
+/// let latency_critical = matchers::timeout_below(Duration::from_millis(500));
+/// ```
+pub fn timeout_below(threshold: Duration) -> RequestTimeout {
+    RequestTimeout {
+        below: Some(threshold),
+    }
+}
+
+impl Condition for RequestTimeout {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.timeout()
+            .is_some_and(|timeout| self.below.is_none_or(|below| *timeout < below))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    #[test]
+    fn test_timeout() {
+        let extensions = Extensions::new();
+        let mut req = request(http::Method::GET, "http://a/");
+
+        assert!(!has_timeout().evaluate(&req, &extensions));
+        assert!(!timeout_below(Duration::MAX).evaluate(&req, &extensions));
+
+        *req.timeout_mut() = Some(Duration::from_millis(200));
+        assert!(has_timeout().evaluate(&req, &extensions));
+        assert!(timeout_below(Duration::from_millis(500)).evaluate(&req, &extensions));
+        assert!(!timeout_below(Duration::from_millis(200)).evaluate(&req, &extensions));
+    }
+}
@@ -0,0 +1,187 @@
+use http::Extensions;
+#[cfg(feature = "opentelemetry")]
+use opentelemetry::trace::TraceContextExt;
+use reqwest::Request;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// Records in the request extensions whether the trace the request belongs to is sampled,
+/// taking precedence over any OpenTelemetry context in [`is_sampled`]. Insert it when sampling
+/// decisions are made elsewhere, for instance by another tracing system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceSampled(pub bool);
+
+/// A [`Condition`] matching requests that are part of a sampled trace. Created by
+/// [`is_sampled`].
+#[derive(Clone, Copy, Debug)]
+pub struct Sampled;
+
+impl_condition_ops!(Sampled);
+
+/// Matches requests that are part of a sampled trace, so that expensive capture middleware only
+/// runs when its output will be kept. A [`TraceSampled`] extension decides if present. With
+/// the `opentelemetry` feature, the sampled flag of the active span of an
+/// [`opentelemetry::Context`] in the request extensions, or else of the current context,
+/// decides next. Requests with neither are not sampled.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::is_sampled();
+/// ```
+pub fn is_sampled() -> Sampled {
+    Sampled
+}
+
+#[cfg(feature = "opentelemetry")]
+fn context_sampled(context: &opentelemetry::Context) -> Option<bool> {
+    context
+        .has_active_span()
+        .then(|| context.span().span_context().is_sampled())
+}
+
+impl Condition for Sampled {
+    fn evaluate(&self, _req: &Request, extensions: &Extensions) -> bool {
+        if let Some(TraceSampled(sampled)) = extensions.get::<TraceSampled>() {
+            return *sampled;
+        }
+        #[cfg(feature = "opentelemetry")]
+        {
+            let sampled = match extensions.get::<opentelemetry::Context>() {
+                Some(context) => context_sampled(context),
+                None => opentelemetry::Context::map_current(context_sampled),
+            };
+            if let Some(sampled) = sampled {
+                return sampled;
+            }
+        }
+        false
+    }
+}
+
+/// A [`Condition`] matching requests made while the current `tracing` span is enabled.
+/// Created by [`span_enabled`].
+#[derive(Clone, Copy, Debug)]
+pub struct SpanEnabled;
+
+impl_condition_ops!(SpanEnabled);
+
+/// Matches requests made while the current `tracing` span is enabled, that is while a
+/// subscriber is interested in it. This says nothing about whether the trace is sampled: with
+/// a typical formatting subscriber every span is enabled. Use [`is_sampled`] for sampling
+/// decisions.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::span_enabled();
+/// ```
+pub fn span_enabled() -> SpanEnabled {
+    SpanEnabled
+}
+
+impl Condition for SpanEnabled {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        !tracing::Span::current().is_disabled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+    use std::sync::Mutex;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata};
+    use tracing_core::span::Current;
+
+    /// A subscriber enabling every span, and tracking the last one entered
+    #[derive(Default)]
+    struct Everything(Mutex<Option<&'static Metadata<'static>>>);
+
+    impl tracing::Subscriber for Everything {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            *self.0.lock().unwrap() = Some(span.metadata());
+            Id::from_u64(1)
+        }
+        fn current_span(&self) -> Current {
+            match *self.0.lock().unwrap() {
+                Some(metadata) => Current::new(Id::from_u64(1), metadata),
+                None => Current::none(),
+            }
+        }
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+        fn event(&self, _event: &Event<'_>) {}
+        fn enter(&self, _span: &Id) {}
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn test_is_sampled() {
+        let req = request(http::Method::GET, "http://a/");
+        let mut extensions = Extensions::new();
+
+        assert!(!is_sampled().evaluate(&req, &extensions));
+
+        extensions.insert(TraceSampled(true));
+        assert!(is_sampled().evaluate(&req, &extensions));
+    }
+
+    #[cfg(feature = "opentelemetry")]
+    #[test]
+    fn test_is_sampled_opentelemetry() {
+        use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceState};
+
+        let req = request(http::Method::GET, "http://a/");
+        let context = |flags| {
+            let span = SpanContext::new(
+                TraceId::from(1),
+                SpanId::from(1),
+                flags,
+                true,
+                TraceState::default(),
+            );
+            opentelemetry::Context::new().with_remote_span_context(span)
+        };
+
+        let mut extensions = Extensions::new();
+        extensions.insert(context(TraceFlags::SAMPLED));
+        assert!(is_sampled().evaluate(&req, &extensions));
+
+        extensions.insert(context(TraceFlags::default()));
+        assert!(!is_sampled().evaluate(&req, &extensions));
+
+        // The current context is used without one in the extensions
+        let _guard = context(TraceFlags::SAMPLED).attach();
+        assert!(is_sampled().evaluate(&req, &Extensions::new()));
+
+        extensions.insert(TraceSampled(true));
+        assert!(is_sampled().evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_span_enabled() {
+        let req = request(http::Method::GET, "http://a/");
+        let extensions = Extensions::new();
+
+        // No subscriber is installed, so no span is enabled
+        {
+            let span = tracing::info_span!("request");
+            let _guard = span.enter();
+            assert!(!span_enabled().evaluate(&req, &extensions));
+        }
+
+        tracing::subscriber::with_default(Everything::default(), || {
+            let span = tracing::info_span!("request");
+            let _guard = span.enter();
+            assert!(span_enabled().evaluate(&req, &extensions));
+            // A span being enabled does not make the trace sampled
+            assert!(!is_sampled().evaluate(&req, &extensions));
+        });
+    }
+}
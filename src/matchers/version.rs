@@ -0,0 +1,46 @@
+use http::Extensions;
+use reqwest::Request;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] matching requests with a given HTTP version. Created by [`version`].
+#[derive(Clone, Copy, Debug)]
+pub struct Version(http::Version);
+
+impl_condition_ops!(Version);
+
+/// Matches requests whose [`Request::version`] is `version`. This is the version requested by
+/// the caller, HTTP/1.1 unless set otherwise, which the client may still negotiate away.
+///
+/// ```
+/// use reqwest_conditional_middleware::matchers;
+///
+/// let condition = matchers::version(http::Version::HTTP_2);
+/// ```
+pub fn version(version: http::Version) -> Version {
+    Version(version)
+}
+
+impl Condition for Version {
+    fn evaluate(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.version() == self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::tests::request;
+
+    #[test]
+    fn test_version() {
+        let extensions = Extensions::new();
+        let mut req = request(http::Method::GET, "http://a/");
+
+        assert!(version(http::Version::HTTP_11).evaluate(&req, &extensions));
+        *req.version_mut() = http::Version::HTTP_2;
+        assert!(version(http::Version::HTTP_2).evaluate(&req, &extensions));
+        assert!(!version(http::Version::HTTP_11).evaluate(&req, &extensions));
+    }
+}
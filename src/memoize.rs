@@ -0,0 +1,155 @@
+use http::Extensions;
+use reqwest::Request;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+type KeyFn = fn(&Request, &Extensions) -> Option<String>;
+
+/// A [`Condition`] that caches the result of an expensive condition for a period of time.
+/// Results are cached per request host by default, or per key returned by a function given to
+/// [`with_key`][Memoize::with_key]. Requests without a key are always evaluated.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::Memoize;
+/// use std::time::Duration;
+///
+/// # fn expensive_check(_req: &Request) -> bool { true }
+/// let condition = Memoize::new(expensive_check, Duration::from_secs(30));
+/// ```
+pub struct Memoize<C, K = KeyFn> {
+    condition: C,
+    key: K,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, Entry>>,
+}
+
+impl_condition_ops!(Memoize<C, K>);
+
+struct Entry {
+    result: bool,
+    expires: Instant,
+}
+
+fn host_key(req: &Request, _extensions: &Extensions) -> Option<String> {
+    req.url().host_str().map(str::to_ascii_lowercase)
+}
+
+impl<C> Memoize<C>
+where
+    C: Condition,
+{
+    /// Caches the result of `condition` per request host for `ttl`
+    pub fn new(condition: C, ttl: Duration) -> Self {
+        Self {
+            condition,
+            key: host_key,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<C, K> Memoize<C, K>
+where
+    C: Condition,
+{
+    /// Caches results per key returned by `key` instead of per host
+    pub fn with_key<F>(self, key: F) -> Memoize<C, F>
+    where
+        F: Fn(&Request, &Extensions) -> Option<String> + Send + Sync + 'static,
+    {
+        Memoize {
+            condition: self.condition,
+            key,
+            ttl: self.ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<C, K> Condition for Memoize<C, K>
+where
+    C: Condition,
+    K: Fn(&Request, &Extensions) -> Option<String> + Send + Sync + 'static,
+{
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        let Some(key) = (self.key)(req, extensions) else {
+            return self.condition.evaluate(req, extensions);
+        };
+
+        let now = Instant::now();
+        if let Some(entry) = self.cache.lock().unwrap().get(&key) {
+            if entry.expires > now {
+                return entry.result;
+            }
+        }
+
+        // The lock is not held while evaluating, so concurrent misses for the same key may
+        // each evaluate the condition
+        let result = self.condition.evaluate(req, extensions);
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, entry| entry.expires > now);
+        cache.insert(
+            key,
+            Entry {
+                result,
+                expires: now + self.ttl,
+            },
+        );
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn counting() -> (impl Condition, Arc<AtomicUsize>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = count.clone();
+        let condition = move |_req: &Request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            true
+        };
+        (condition, count)
+    }
+
+    fn request(url: &str) -> Request {
+        Request::new(http::Method::GET, url.parse().unwrap())
+    }
+
+    #[test]
+    fn test_memoize_per_host() {
+        let (condition, count) = counting();
+        let condition = Memoize::new(condition, Duration::from_secs(60));
+        let extensions = Extensions::new();
+
+        assert!(condition.evaluate(&request("http://a.example.com/1"), &extensions));
+        assert!(condition.evaluate(&request("http://A.example.com/2"), &extensions));
+        assert_eq!(1, count.load(Ordering::SeqCst));
+
+        assert!(condition.evaluate(&request("http://b.example.com/1"), &extensions));
+        assert_eq!(2, count.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_memoize_expires() {
+        let (condition, count) = counting();
+        let condition = Memoize::new(condition, Duration::ZERO)
+            .with_key(|req: &Request, _extensions: &Extensions| Some(req.url().path().to_owned()));
+        let extensions = Extensions::new();
+
+        condition.evaluate(&request("http://example.com/1"), &extensions);
+        condition.evaluate(&request("http://example.com/1"), &extensions);
+        assert_eq!(2, count.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,233 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::Request;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{AsyncCondition, Decision};
+
+/// Tells whether the machine is online, consulted by an [`Online`] condition. Implementations
+/// may for instance connect to a well-known host or ask the operating system's network
+/// manager.
+///
+/// ```
+/// use reqwest_conditional_middleware::ConnectivityProbe;
+/// use std::time::Duration;
+/// use tokio::net::TcpStream;
+///
+/// struct Tcp;
+///
+/// #[async_trait::async_trait]
+/// impl ConnectivityProbe for Tcp {
+///     async fn is_online(&self) -> bool {
+///         let connect = TcpStream::connect("1.1.1.1:443");
+///         matches!(
+///             tokio::time::timeout(Duration::from_secs(1), connect).await,
+///             Ok(Ok(_))
+///         )
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait ConnectivityProbe: Send + Sync + 'static {
+    /// Returns true if the machine is online
+    async fn is_online(&self) -> bool;
+}
+
+#[async_trait]
+impl<P> ConnectivityProbe for Arc<P>
+where
+    P: ConnectivityProbe + ?Sized,
+{
+    async fn is_online(&self) -> bool {
+        (**self).is_online().await
+    }
+}
+
+/// An [`AsyncCondition`] that is true while a [`ConnectivityProbe`] reports the machine as
+/// online, so that network-augmenting middleware, such as telemetry uploads or revocation
+/// checks, is skipped when working offline. The probe's answer is cached for 30 seconds by
+/// default, see [`recheck_every`](Online::recheck_every), and a single probe runs at a time:
+/// requests arriving while it runs wait for its answer. Clones share the cached answer.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, Online};
+/// # use reqwest_conditional_middleware::Passthrough as Telemetry;
+/// # struct Probe;
+/// # #[async_trait::async_trait]
+/// # impl reqwest_conditional_middleware::ConnectivityProbe for Probe {
+/// #     async fn is_online(&self) -> bool { true }
+/// # }
+///
+/// let conditional = ConditionalMiddleware::new(Telemetry, Online::new(Probe));
+/// ```
+#[derive(Debug)]
+pub struct Online<P> {
+    probe: Arc<P>,
+    recheck: Duration,
+    last: Arc<Mutex<Option<(Instant, bool)>>>,
+    probing: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl<P> Clone for Online<P> {
+    fn clone(&self) -> Self {
+        Self {
+            probe: self.probe.clone(),
+            recheck: self.recheck,
+            last: self.last.clone(),
+            probing: self.probing.clone(),
+        }
+    }
+}
+
+impl<P> Online<P>
+where
+    P: ConnectivityProbe,
+{
+    /// Creates a condition that is true while `probe` reports the machine as online
+    pub fn new(probe: P) -> Self {
+        Self {
+            probe: Arc::new(probe),
+            recheck: Duration::from_secs(30),
+            last: Arc::default(),
+            probing: Arc::default(),
+        }
+    }
+
+    /// Sets how long the probe's answer is trusted before it is asked again. A zero duration
+    /// probes on every request.
+    pub fn recheck_every(mut self, recheck: Duration) -> Self {
+        self.recheck = recheck;
+        self
+    }
+
+    /// Forgets the cached answer, so that the next request probes again, for instance when the
+    /// application is told that the network changed
+    pub fn invalidate(&self) {
+        *self.last.lock().unwrap() = None;
+    }
+
+    /// Returns whether the machine is online, probing it if the cached answer is stale
+    pub async fn is_online(&self) -> bool {
+        let cached = |since: Instant| match *self.last.lock().unwrap() {
+            Some((at, online)) if at >= since || at.elapsed() < self.recheck => Some(online),
+            _ => None,
+        };
+        let started = Instant::now();
+        if let Some(online) = cached(started) {
+            return online;
+        }
+        // Only one request probes, the others use its answer once it is done
+        let _probing = self.probing.lock().await;
+        if let Some(online) = cached(started) {
+            return online;
+        }
+
+        let online = self.probe.is_online().await;
+        if !online {
+            tracing::debug!("offline, skipping network-dependent middleware");
+        }
+        *self.last.lock().unwrap() = Some((Instant::now(), online));
+        online
+    }
+}
+
+#[async_trait]
+impl<P> AsyncCondition for Online<P>
+where
+    P: ConnectivityProbe,
+{
+    type Error = Infallible;
+
+    async fn evaluate_async(
+        &self,
+        _req: &Request,
+        _extensions: &Extensions,
+    ) -> Result<Decision, Self::Error> {
+        Ok(self.is_online().await.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct Probe {
+        online: AtomicBool,
+        probes: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ConnectivityProbe for Probe {
+        async fn is_online(&self) -> bool {
+            self.probes.fetch_add(1, Ordering::Relaxed);
+            self.online.load(Ordering::Relaxed)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_online() {
+        let extensions = Extensions::new();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let probe = Arc::new(Probe::default());
+        let online = Online::new(probe.clone());
+
+        let decision = online.evaluate_async(&req, &extensions).await.unwrap();
+        assert!(matches!(decision, Decision::Skip));
+
+        // The cached answer is used until it is invalidated
+        probe.online.store(true, Ordering::Relaxed);
+        let decision = online
+            .clone()
+            .evaluate_async(&req, &extensions)
+            .await
+            .unwrap();
+        assert!(matches!(decision, Decision::Skip));
+        assert_eq!(1, probe.probes.load(Ordering::Relaxed));
+
+        online.invalidate();
+        let decision = online.evaluate_async(&req, &extensions).await.unwrap();
+        assert!(matches!(decision, Decision::Run));
+        assert_eq!(2, probe.probes.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_single_probe() {
+        struct Slow(AtomicUsize);
+
+        #[async_trait]
+        impl ConnectivityProbe for Slow {
+            async fn is_online(&self) -> bool {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                true
+            }
+        }
+
+        let probe = Arc::new(Slow(AtomicUsize::new(0)));
+        let online = Online::new(probe.clone()).recheck_every(Duration::ZERO);
+        let checks: Vec<_> = (0..10)
+            .map(|_| {
+                let online = online.clone();
+                tokio::spawn(async move { online.is_online().await })
+            })
+            .collect();
+        for check in checks {
+            assert!(check.await.unwrap());
+        }
+        assert_eq!(1, probe.0.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_recheck_every() {
+        let probe = Arc::new(Probe::default());
+        let online = Online::new(probe.clone()).recheck_every(Duration::ZERO);
+
+        assert!(!online.is_online().await);
+        probe.online.store(true, Ordering::Relaxed);
+        assert!(online.is_online().await);
+    }
+}
@@ -0,0 +1,118 @@
+use http::Extensions;
+use reqwest::Request;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+/// A [`Condition`] that is true at most `n` times per window, using a token bucket, so that an
+/// expensive middleware can not amplify load during traffic spikes. The bucket starts full and
+/// refills continuously at `n` tokens per window; each request that finds a token takes it and
+/// matches. Bursts of up to `n` requests are allowed. Clones share the same bucket.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, RateLimit};
+/// use std::time::Duration;
+/// # use reqwest_conditional_middleware::Passthrough as Enrichment;
+///
+/// let conditional =
+///     ConditionalMiddleware::new(Enrichment, RateLimit::new(100, Duration::from_secs(1)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RateLimit {
+    capacity: f64,
+    per_second: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+impl_condition_ops!(RateLimit);
+
+impl RateLimit {
+    /// Creates a condition true for at most `n` requests per `window`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is zero.
+    pub fn new(n: u32, window: Duration) -> Self {
+        assert!(!window.is_zero(), "RateLimit requires a non-zero window");
+        let capacity = f64::from(n);
+        Self {
+            capacity,
+            per_second: capacity / window.as_secs_f64(),
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: capacity,
+                refilled: Instant::now(),
+            })),
+        }
+    }
+
+    /// Refills the bucket up to `now` and takes a token if there is one
+    fn take(&self, now: Instant) -> bool {
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.capacity);
+        bucket.refilled = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Condition for RateLimit {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        self.take(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit() {
+        let condition = RateLimit::new(3, Duration::from_millis(300));
+        let clone = condition.clone();
+        let start = condition.bucket.lock().unwrap().refilled;
+
+        assert!(condition.take(start));
+        assert!(clone.take(start));
+        assert!(condition.take(start));
+        assert!(!clone.take(start));
+
+        // One token is refilled every 100ms
+        let later = start + Duration::from_millis(120);
+        assert!(condition.take(later));
+        assert!(!condition.take(later));
+
+        let later = later + Duration::from_millis(400);
+        let allowed = (0..10).filter(|_| condition.take(later)).count();
+        assert_eq!(3, allowed);
+    }
+
+    #[test]
+    fn test_rate_limit_evaluate() {
+        let extensions = Extensions::new();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let condition = RateLimit::new(1, Duration::from_secs(3600));
+        assert!(condition.evaluate(&req, &extensions));
+        assert!(!condition.evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_rate_limit_zero() {
+        let condition = RateLimit::new(0, Duration::from_secs(1));
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        assert!(!condition.evaluate(&req, &Extensions::new()));
+    }
+}
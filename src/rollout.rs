@@ -0,0 +1,229 @@
+use http::header::HeaderName;
+use http::Extensions;
+use reqwest::Request;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::condition::impl_condition_ops;
+use crate::matchers::header_name;
+use crate::Condition;
+
+/// The number of buckets keys are hashed into, giving rollouts a resolution of 0.01%
+pub(crate) const BUCKETS: u64 = 10_000;
+
+/// A 64-bit FNV-1a hasher. Unlike the standard library's hasher its output is specified, so
+/// keys land in the same bucket across processes, platforms and Rust versions.
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
+    pub(crate) fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Key {
+    Host,
+    Header(HeaderName),
+    Extension(fn(&Extensions, &mut Fnv) -> bool),
+}
+
+fn hash_extension<T>(extensions: &Extensions, hasher: &mut Fnv) -> bool
+where
+    T: Hash + Send + Sync + 'static,
+{
+    extensions.get::<T>().map(|key| key.hash(hasher)).is_some()
+}
+
+pub(crate) fn threshold(percentage: f64) -> u32 {
+    (percentage.clamp(0.0, 100.0) * (BUCKETS / 100) as f64).round() as u32
+}
+
+/// A [`Condition`] enabling a middleware for a percentage of requests, chosen by hashing a
+/// stable key so that the same key always gets the same decision. New middleware, such as a
+/// new signing scheme, can so be rolled out gradually to hosts, users or tenants. Requests
+/// without a key never match.
+///
+/// Raising the percentage keeps every previously enabled key enabled. The percentage can be
+/// changed at runtime with [`set_percentage`](Rollout::set_percentage), and clones share it.
+/// Keys are hashed with FNV-1a, so assignments are the same in every process.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, Rollout};
+/// # use reqwest_conditional_middleware::Passthrough as NewSigner;
+///
+/// let rollout = Rollout::by_header("x-tenant-id", 5.0).with_salt("new-signer");
+/// let conditional = ConditionalMiddleware::new(NewSigner, rollout.clone());
+///
+/// // Later, once the first tenants are happy
+/// rollout.set_percentage(25.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Rollout {
+    key: Key,
+    salt: String,
+    threshold: Arc<AtomicU32>,
+}
+
+impl_condition_ops!(Rollout);
+
+impl Rollout {
+    fn new(key: Key, percentage: f64) -> Self {
+        Self {
+            key,
+            salt: String::new(),
+            threshold: Arc::new(AtomicU32::new(threshold(percentage))),
+        }
+    }
+
+    /// Enables `percentage` percent of request hosts, compared case-insensitively
+    pub fn by_host(percentage: f64) -> Self {
+        Self::new(Key::Host, percentage)
+    }
+
+    /// Enables `percentage` percent of the values of header `name`. Requests without the header
+    /// never match.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    pub fn by_header<N>(name: N, percentage: f64) -> Self
+    where
+        N: TryInto<HeaderName>,
+        N::Error: Debug,
+    {
+        Self::new(Key::Header(header_name(name)), percentage)
+    }
+
+    /// Enables `percentage` percent of the values of the request extension `T`, such as a user
+    /// or tenant id inserted by the caller. Requests without the extension never match.
+    /// Assignments are stable as long as the [`Hash`] implementation of `T` is.
+    pub fn by_extension<T>(percentage: f64) -> Self
+    where
+        T: Hash + Send + Sync + 'static,
+    {
+        Self::new(Key::Extension(hash_extension::<T>), percentage)
+    }
+
+    /// Hashes `salt` along with each key, so that separate rollouts by the same key enable
+    /// different keys rather than always starting with the same ones
+    pub fn with_salt(mut self, salt: impl Into<String>) -> Self {
+        self.salt = salt.into();
+        self
+    }
+
+    /// Changes the percentage of keys that are enabled, from 0 to 100 with a resolution of
+    /// 0.01. Values outside of that range are clamped.
+    pub fn set_percentage(&self, percentage: f64) {
+        self.threshold
+            .store(threshold(percentage), Ordering::Relaxed);
+    }
+
+    /// Returns the percentage of keys that are enabled
+    pub fn percentage(&self) -> f64 {
+        f64::from(self.threshold.load(Ordering::Relaxed)) / (BUCKETS / 100) as f64
+    }
+}
+
+impl Condition for Rollout {
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        let mut hasher = Fnv::new();
+        hasher.write(self.salt.as_bytes());
+        hasher.write_u8(0xff);
+
+        let found = match &self.key {
+            Key::Host => req.url().host_str().map(|host| {
+                for byte in host.bytes() {
+                    hasher.write_u8(byte.to_ascii_lowercase());
+                }
+            }),
+            Key::Header(name) => req
+                .headers()
+                .get(name)
+                .map(|value| hasher.write(value.as_bytes())),
+            Key::Extension(hash) => hash(extensions, &mut hasher).then_some(()),
+        };
+        found.is_some()
+            && hasher.finish() % BUCKETS < u64::from(self.threshold.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled(rollout: &Rollout, hosts: std::ops::Range<u32>) -> Vec<u32> {
+        let extensions = Extensions::new();
+        hosts
+            .filter(|i| {
+                let url = format!("http://host-{i}.example.com").parse().unwrap();
+                rollout.evaluate(&Request::new(http::Method::GET, url), &extensions)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fnv() {
+        let mut hasher = Fnv::new();
+        hasher.write(b"foobar");
+        assert_eq!(0x85944171f73967e8, hasher.finish());
+    }
+
+    #[test]
+    fn test_rollout_by_host() {
+        let rollout = Rollout::by_host(0.0);
+        assert!(enabled(&rollout, 0..1000).is_empty());
+
+        rollout.set_percentage(10.0);
+        let ten = enabled(&rollout, 0..1000);
+        assert!((50..150).contains(&ten.len()), "{}", ten.len());
+        assert_eq!(ten, enabled(&rollout.clone(), 0..1000));
+
+        rollout.set_percentage(50.0);
+        assert_eq!(50.0, rollout.percentage());
+        let fifty = enabled(&rollout, 0..1000);
+        assert!((400..600).contains(&fifty.len()), "{}", fifty.len());
+        assert!(ten.iter().all(|i| fifty.contains(i)));
+
+        let salted = rollout.clone().with_salt("other");
+        assert_ne!(fifty, enabled(&salted, 0..1000));
+
+        rollout.set_percentage(150.0);
+        assert_eq!(1000, enabled(&rollout, 0..1000).len());
+    }
+
+    #[test]
+    fn test_rollout_by_header_and_extension() {
+        #[derive(Clone, Hash)]
+        struct UserId(u64);
+
+        let by_header = Rollout::by_header("x-user", 100.0);
+        let by_extension = Rollout::by_extension::<UserId>(100.0);
+        let mut req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let mut extensions = Extensions::new();
+
+        assert!(!by_header.evaluate(&req, &extensions));
+        assert!(!by_extension.evaluate(&req, &extensions));
+
+        req.headers_mut()
+            .insert("x-user", http::HeaderValue::from_static("42"));
+        extensions.insert(UserId(42));
+        assert!(by_header.evaluate(&req, &extensions));
+        assert!(by_extension.evaluate(&req, &extensions));
+    }
+}
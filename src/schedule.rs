@@ -0,0 +1,279 @@
+use http::Extensions;
+use reqwest::Request;
+#[cfg(feature = "cron")]
+use std::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// A day of the week, for the recurring windows of a [`Schedule`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Weekday {
+    /// Monday
+    Monday,
+    /// Tuesday
+    Tuesday,
+    /// Wednesday
+    Wednesday,
+    /// Thursday
+    Thursday,
+    /// Friday
+    Friday,
+    /// Saturday
+    Saturday,
+    /// Sunday
+    Sunday,
+}
+
+impl Weekday {
+    /// Monday to Friday
+    pub const WORKDAYS: [Weekday; 5] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+    ];
+
+    /// Every day of the week
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Parses a `HH:MM` time of day into seconds since midnight, allowing `24:00` as the end of
+/// the day
+fn time_of_day(time: &str) -> Option<u64> {
+    let (hours, minutes) = time.split_once(':')?;
+    let (hours, minutes): (u64, u64) = (hours.parse().ok()?, minutes.parse().ok()?);
+    let seconds = (hours * 60 + minutes) * 60;
+    (minutes < 60 && seconds <= DAY).then_some(seconds)
+}
+
+#[derive(Clone, Debug)]
+enum Window {
+    Weekly {
+        days: u8,
+        start: u64,
+        end: u64,
+    },
+    Between {
+        start: SystemTime,
+        end: SystemTime,
+    },
+    #[cfg(feature = "cron")]
+    Cron {
+        schedule: Box<cron::Schedule>,
+        duration: Duration,
+    },
+}
+
+impl Window {
+    fn contains(&self, time: SystemTime, offset: i64) -> bool {
+        match self {
+            Window::Weekly { days, start, end } => {
+                let Ok(since_epoch) = time.duration_since(UNIX_EPOCH) else {
+                    return false;
+                };
+                let local = since_epoch.as_secs().saturating_add_signed(offset);
+                let (day, seconds) = (local / DAY, local % DAY);
+                // The epoch was a Thursday
+                let weekday = |day: u64| 1u8 << ((day + 3) % 7);
+
+                if start <= end {
+                    days & weekday(day) != 0 && (*start..*end).contains(&seconds)
+                } else {
+                    // The window spans midnight and belongs to the day it starts on
+                    (days & weekday(day) != 0 && seconds >= *start)
+                        || (days & weekday(day + 6) != 0 && seconds < *end)
+                }
+            }
+            Window::Between { start, end } => (*start..*end).contains(&time),
+            #[cfg(feature = "cron")]
+            Window::Cron { schedule, duration } => {
+                let since = chrono::DateTime::<chrono::Utc>::from(time - *duration);
+                schedule
+                    .after(&since)
+                    .next()
+                    .is_some_and(|fired| SystemTime::from(fired) <= time)
+            }
+        }
+    }
+}
+
+/// A [`Condition`] that is true only within a set of time windows, such as business hours or
+/// a maintenance window, so that a middleware can be enabled or disabled for known periods.
+/// A schedule without windows is never active. Recurring windows are in UTC unless an offset
+/// is set with [`with_utc_offset`](Schedule::with_utc_offset).
+///
+/// With the `cron` feature windows can also start at the times described by a cron
+/// expression.
+///
+/// ```
+/// use reqwest_conditional_middleware::{condition, ConditionalMiddleware, Schedule, Weekday};
+/// use std::time::{Duration, SystemTime};
+/// # use reqwest_conditional_middleware::Passthrough as AggressiveRetries;
+///
+/// let busy = Schedule::new()
+///     .weekly(Weekday::WORKDAYS, "09:00", "17:00")
+///     .with_utc_offset(-7 * 60);
+/// let conditional = ConditionalMiddleware::new(AggressiveRetries, condition::not(busy));
+///
+/// let start = SystemTime::now() + Duration::from_secs(3600);
+/// let maintenance = Schedule::new().between(start, start + Duration::from_secs(1800));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Schedule {
+    windows: Vec<Window>,
+    offset: i64,
+}
+
+impl_condition_ops!(Schedule);
+
+impl Schedule {
+    /// Creates a schedule without any windows
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a window recurring on each of `days` from `start` to `end`, both given as `HH:MM`
+    /// times of day. A window whose end is before its start spans midnight, so
+    /// `weekly([Weekday::Friday], "22:00", "06:00")` lasts from Friday night to Saturday
+    /// morning. Use `24:00` for an end at midnight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` or `end` is not a valid time of day.
+    pub fn weekly(
+        mut self,
+        days: impl IntoIterator<Item = Weekday>,
+        start: &str,
+        end: &str,
+    ) -> Self {
+        let start = time_of_day(start).expect("invalid start time");
+        let end = time_of_day(end).expect("invalid end time");
+        let days = days.into_iter().fold(0, |days, day| days | day.bit());
+        self.windows.push(Window::Weekly { days, start, end });
+        self
+    }
+
+    /// Adds a single window from `start`, inclusive, to `end`, exclusive
+    pub fn between(mut self, start: SystemTime, end: SystemTime) -> Self {
+        self.windows.push(Window::Between { start, end });
+        self
+    }
+
+    /// Adds windows lasting `duration` from each time matched by the cron `expression`, which
+    /// includes a seconds field: `"0 0 2 * * Sun"` is every Sunday at 02:00. Cron expressions
+    /// are evaluated in UTC regardless of [`with_utc_offset`](Schedule::with_utc_offset).
+    /// Available with the `cron` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `expression` is not a valid cron expression.
+    #[cfg(feature = "cron")]
+    pub fn cron(mut self, expression: &str, duration: Duration) -> Self {
+        let schedule = expression.parse().expect("invalid cron expression");
+        self.windows.push(Window::Cron {
+            schedule: Box::new(schedule),
+            duration,
+        });
+        self
+    }
+
+    /// Sets the offset from UTC, in minutes, of the times of day of recurring windows
+    pub fn with_utc_offset(mut self, minutes: i32) -> Self {
+        self.offset = i64::from(minutes) * 60;
+        self
+    }
+
+    /// Returns true if `time` is within one of the windows
+    pub fn is_active_at(&self, time: SystemTime) -> bool {
+        self.windows
+            .iter()
+            .any(|window| window.contains(time, self.offset))
+    }
+}
+
+impl Condition for Schedule {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        self.is_active_at(SystemTime::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Returns the time at `hours:minutes` UTC on 2024-01-01, a Monday, plus `days`
+    fn at(days: u64, hours: u64, minutes: u64) -> SystemTime {
+        const MONDAY: u64 = 1_704_067_200;
+        UNIX_EPOCH + Duration::from_secs(MONDAY + days * DAY + (hours * 60 + minutes) * 60)
+    }
+
+    #[test]
+    fn test_weekly() {
+        let schedule = Schedule::new().weekly(Weekday::WORKDAYS, "09:00", "17:30");
+
+        assert!(schedule.is_active_at(at(0, 9, 0)));
+        assert!(schedule.is_active_at(at(4, 17, 29)));
+        assert!(!schedule.is_active_at(at(0, 17, 30)));
+        assert!(!schedule.is_active_at(at(1, 8, 59)));
+        assert!(!schedule.is_active_at(at(5, 12, 0)));
+
+        let schedule = schedule.with_utc_offset(-120);
+        assert!(schedule.is_active_at(at(0, 11, 0)));
+        assert!(!schedule.is_active_at(at(0, 10, 59)));
+    }
+
+    #[test]
+    fn test_weekly_overnight() {
+        let schedule = Schedule::new().weekly([Weekday::Friday], "22:00", "06:00");
+
+        assert!(schedule.is_active_at(at(4, 23, 0)));
+        assert!(schedule.is_active_at(at(5, 5, 59)));
+        assert!(!schedule.is_active_at(at(5, 6, 0)));
+        assert!(!schedule.is_active_at(at(4, 5, 0)));
+        assert!(!schedule.is_active_at(at(3, 23, 0)));
+    }
+
+    #[test]
+    fn test_between() {
+        let schedule = Schedule::new().between(at(0, 2, 0), at(0, 3, 0));
+
+        assert!(schedule.is_active_at(at(0, 2, 0)));
+        assert!(!schedule.is_active_at(at(0, 3, 0)));
+        assert!(!Schedule::new().is_active_at(at(0, 2, 30)));
+    }
+
+    #[cfg(feature = "cron")]
+    #[test]
+    fn test_cron() {
+        let schedule = Schedule::new().cron("0 0 2 * * Sun", Duration::from_secs(3600));
+
+        assert!(schedule.is_active_at(at(6, 2, 0)));
+        assert!(schedule.is_active_at(at(6, 2, 59)));
+        assert!(!schedule.is_active_at(at(6, 3, 0)));
+        assert!(!schedule.is_active_at(at(5, 2, 30)));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid start time")]
+    fn test_invalid_time() {
+        Schedule::new().weekly(Weekday::ALL, "9am", "17:00");
+    }
+}
@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] bounding how many requests run a middleware at once with a
+/// [`Semaphore`]: the middleware only runs when a permit is immediately available, and
+/// requests finding none pass straight through without waiting. The middleware must be
+/// wrapped with [`wrap`](SemaphoreGate::wrap), which holds the permit while it runs; the
+/// condition itself only checks that a permit is available, so that a request is not sent to
+/// the wrapper in vain. Clones share the same semaphore.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, SemaphoreGate};
+/// # use reqwest_conditional_middleware::Passthrough as Scanner;
+///
+/// let gate = SemaphoreGate::new(4);
+/// let conditional = ConditionalMiddleware::new(gate.wrap(Scanner), gate.clone());
+/// ```
+#[derive(Clone, Debug)]
+pub struct SemaphoreGate {
+    semaphore: Arc<Semaphore>,
+}
+
+impl_condition_ops!(SemaphoreGate);
+
+impl SemaphoreGate {
+    /// Creates a gate letting up to `permits` requests run the middleware concurrently
+    pub fn new(permits: usize) -> Self {
+        Self::from_semaphore(Arc::new(Semaphore::new(permits)))
+    }
+
+    /// Creates a gate sharing the permits of `semaphore`, for instance with other work
+    /// bounded by it
+    pub fn from_semaphore(semaphore: Arc<Semaphore>) -> Self {
+        Self { semaphore }
+    }
+
+    /// Wraps `middleware` so that it only runs while holding a permit
+    pub fn wrap<M>(&self, middleware: M) -> Gated<M>
+    where
+        M: Middleware,
+    {
+        Gated {
+            inner: middleware,
+            semaphore: self.semaphore.clone(),
+        }
+    }
+
+    /// Returns the number of permits currently available
+    pub fn available_permits(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+}
+
+impl Condition for SemaphoreGate {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        self.semaphore.available_permits() > 0
+    }
+}
+
+/// A middleware running the middleware it wraps only while holding a permit of a
+/// [`SemaphoreGate`]. Created by [`SemaphoreGate::wrap`].
+#[derive(Clone, Debug)]
+pub struct Gated<M> {
+    inner: M,
+    semaphore: Arc<Semaphore>,
+}
+
+#[async_trait]
+impl<M> Middleware for Gated<M>
+where
+    M: Middleware,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        // Other requests may have taken the last permit since the condition was evaluated
+        match self.semaphore.clone().try_acquire_owned() {
+            Ok(_permit) => self.inner.handle(req, extensions, next).await,
+            Err(_) => next.run(req, extensions).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{CheckMiddleware, End};
+    use crate::ConditionalMiddleware;
+
+    #[tokio::test]
+    async fn test_semaphore_gate() {
+        let gate = SemaphoreGate::new(1);
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(gate.wrap(check), gate.clone()))
+                .with(End)
+                .build();
+
+        let permit = gate.semaphore.clone().try_acquire_owned().unwrap();
+        assert!(!gate.evaluate(
+            &client.get("http://localhost").build().unwrap(),
+            &Extensions::new()
+        ));
+        client.get("http://localhost").send().await.unwrap();
+        assert!(!*test.lock().unwrap());
+
+        drop(permit);
+        client.get("http://localhost").send().await.unwrap();
+        assert!(*test.lock().unwrap());
+        assert_eq!(1, gate.available_permits());
+    }
+
+    #[tokio::test]
+    async fn test_gated_without_permit() {
+        let gate = SemaphoreGate::new(0);
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        // Even when the condition is bypassed, the wrapper passes requests through
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(gate.wrap(check))
+                .with(End)
+                .build();
+
+        client.get("http://localhost").send().await.unwrap();
+        assert!(!*test.lock().unwrap());
+    }
+}
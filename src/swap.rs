@@ -0,0 +1,250 @@
+use arc_swap::ArcSwap;
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+use std::sync::Arc;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] that can be replaced at runtime through a [`ConditionHandle`], without
+/// rebuilding the client it is installed in. Evaluating the condition does not take a lock.
+/// Clones share the same underlying condition.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, SwappableCondition};
+/// # use reqwest_conditional_middleware::Passthrough as Inner;
+///
+/// let condition = SwappableCondition::new(|_req: &Request| false);
+/// let handle = condition.handle();
+/// let conditional = ConditionalMiddleware::new(Inner, condition);
+///
+/// // Later, from anywhere that holds the handle
+/// handle.set(|req: &Request| req.method() == http::Method::GET);
+/// ```
+#[derive(Clone)]
+pub struct SwappableCondition {
+    current: Arc<ArcSwap<Box<dyn Condition>>>,
+}
+
+impl_condition_ops!(SwappableCondition);
+
+impl SwappableCondition {
+    /// Creates a swappable condition that initially evaluates `condition`
+    pub fn new<C>(condition: C) -> Self
+    where
+        C: Condition,
+    {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(Box::new(condition))),
+        }
+    }
+
+    /// Creates a swappable condition that initially evaluates a condition that is already
+    /// boxed, such as a rule set compiled at runtime, without boxing it again
+    pub fn from_boxed(condition: Box<dyn Condition>) -> Self {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(condition)),
+        }
+    }
+
+    /// Returns a handle that can be used to replace the condition
+    pub fn handle(&self) -> ConditionHandle {
+        ConditionHandle {
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl Condition for SwappableCondition {
+    fn evaluate(&self, req: &Request, extensions: &Extensions) -> bool {
+        self.current.load().evaluate(req, extensions)
+    }
+}
+
+/// A handle for replacing the condition of a [`SwappableCondition`]. Handles are cheap to
+/// clone and all clones refer to the same condition.
+#[derive(Clone)]
+pub struct ConditionHandle {
+    current: Arc<ArcSwap<Box<dyn Condition>>>,
+}
+
+impl ConditionHandle {
+    /// Replaces the condition. Requests that are already evaluating the previous condition
+    /// finish with it, all later requests evaluate `condition`.
+    pub fn set<C>(&self, condition: C)
+    where
+        C: Condition,
+    {
+        self.current.store(Arc::new(Box::new(condition)));
+    }
+
+    /// Replaces the condition with one that is already boxed, such as a rule set compiled at
+    /// runtime, without boxing it again
+    pub fn set_boxed(&self, condition: Box<dyn Condition>) {
+        self.current.store(Arc::new(condition));
+    }
+}
+
+/// A [`Middleware`][reqwest_middleware::Middleware] that forwards to a middleware which can be
+/// replaced at runtime through a [`MiddlewareHandle`]. Wrapping the inner middleware of a
+/// [`ConditionalMiddleware`][crate::ConditionalMiddleware] in this allows it to be rotated (for
+/// instance when signing credentials change) while the conditional stays installed in the
+/// client. Clones share the same underlying middleware.
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, SwappableMiddleware};
+/// # use reqwest_conditional_middleware::Passthrough as Signer;
+///
+/// let inner = SwappableMiddleware::new(Signer);
+/// let handle = inner.handle();
+/// let conditional = ConditionalMiddleware::new(inner, |_req: &Request| true);
+///
+/// // Later, once new credentials are available
+/// handle.set(Signer);
+/// ```
+#[derive(Clone)]
+pub struct SwappableMiddleware {
+    current: Arc<ArcSwap<Box<dyn Middleware>>>,
+}
+
+impl SwappableMiddleware {
+    /// Creates a swappable middleware that initially forwards to `middleware`
+    pub fn new<M>(middleware: M) -> Self
+    where
+        M: Middleware,
+    {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(Box::new(middleware))),
+        }
+    }
+
+    /// Returns a handle that can be used to replace the middleware
+    pub fn handle(&self) -> MiddlewareHandle {
+        MiddlewareHandle {
+            current: self.current.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for SwappableMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let middleware = self.current.load_full();
+        middleware.handle(req, extensions, next).await
+    }
+}
+
+/// A handle for replacing the middleware of a [`SwappableMiddleware`]. Handles are cheap to
+/// clone and all clones refer to the same middleware.
+#[derive(Clone)]
+pub struct MiddlewareHandle {
+    current: Arc<ArcSwap<Box<dyn Middleware>>>,
+}
+
+impl MiddlewareHandle {
+    /// Replaces the middleware. Requests already being handled by the previous middleware
+    /// finish with it, all later requests are handled by `middleware`.
+    pub fn set<M>(&self, middleware: M)
+    where
+        M: Middleware,
+    {
+        self.current.store(Arc::new(Box::new(middleware)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{CheckMiddleware, End};
+    use crate::ConditionalMiddleware;
+
+    #[tokio::test]
+    async fn test_replace_condition() {
+        let check = CheckMiddleware::new();
+        let test = check.checker();
+        let condition = SwappableCondition::new(|_req: &Request| false);
+        let handle = condition.handle();
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(check, condition))
+                .with(End)
+                .build();
+
+        client.get("http://localhost").send().await.unwrap();
+        assert!(!*test.lock().unwrap());
+
+        handle.set(|_req: &Request| true);
+
+        client.get("http://localhost").send().await.unwrap();
+        assert!(*test.lock().unwrap());
+    }
+
+    #[test]
+    fn test_from_boxed() {
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let extensions = Extensions::new();
+        let boxed: Box<dyn Condition> = Box::new(|_req: &Request| true);
+        let condition = SwappableCondition::from_boxed(boxed);
+        assert!(condition.evaluate(&req, &extensions));
+
+        condition.handle().set(|_req: &Request| false);
+        assert!(!condition.evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_publish_while_evaluating() {
+        let condition = SwappableCondition::new(|_req: &Request| false);
+        let handle = condition.handle();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+
+        std::thread::scope(|scope| {
+            let readers: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let extensions = Extensions::new();
+                        while !condition.evaluate(&req, &extensions) {}
+                    })
+                })
+                .collect();
+            for _ in 0..100 {
+                handle.set(|_req: &Request| false);
+            }
+            handle.set_boxed(Box::new(|_req: &Request| true));
+            for reader in readers {
+                reader.join().unwrap();
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn test_replace_middleware() {
+        let first = CheckMiddleware::new();
+        let first_test = first.checker();
+        let second = CheckMiddleware::new();
+        let second_test = second.checker();
+        let inner = SwappableMiddleware::new(first);
+        let handle = inner.handle();
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(ConditionalMiddleware::new(inner, |_req: &Request| true))
+                .with(End)
+                .build();
+
+        handle.set(second);
+
+        client.get("http://localhost").send().await.unwrap();
+        assert!(!*first_test.lock().unwrap());
+        assert!(*second_test.lock().unwrap());
+    }
+}
@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+
+use crate::{Condition, Passthrough};
+
+/// A [`Middleware`][reqwest_middleware::Middleware] holding an ordered list of arms, each
+/// pairing a [`Condition`] with a middleware. For each request the conditions are evaluated
+/// in order and the middleware of the first arm that matches is run. When no arm matches the
+/// default middleware is run, which passes the request along to the next middleware unless
+/// one is set with [`otherwise`][ConditionalSwitch::otherwise].
+///
+/// ```
+/// use reqwest::Request;
+/// use reqwest_conditional_middleware::ConditionalSwitch;
+/// # use reqwest_conditional_middleware::Passthrough as AuthA;
+/// # use reqwest_conditional_middleware::Passthrough as AuthB;
+///
+/// let switch = ConditionalSwitch::new()
+///     .when(|req: &Request| req.url().host_str() == Some("x.example.com"), AuthA)
+///     .when(|req: &Request| req.url().host_str() == Some("y.example.com"), AuthB);
+/// ```
+pub struct ConditionalSwitch {
+    arms: Vec<(Box<dyn Condition>, Box<dyn Middleware>)>,
+    default: Box<dyn Middleware>,
+}
+
+impl ConditionalSwitch {
+    /// Creates a switch with no arms, which passes every request along to the next middleware
+    pub fn new() -> Self {
+        Self {
+            arms: Vec::new(),
+            default: Box::new(Passthrough),
+        }
+    }
+
+    /// Appends an arm that runs `middleware` when `condition` is the first to evaluate to true
+    pub fn when<C, M>(mut self, condition: C, middleware: M) -> Self
+    where
+        C: Condition,
+        M: Middleware,
+    {
+        self.arms.push((Box::new(condition), Box::new(middleware)));
+        self
+    }
+
+    /// Sets the middleware that is run when none of the arms match
+    pub fn otherwise<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware,
+    {
+        self.default = Box::new(middleware);
+        self
+    }
+}
+
+impl Default for ConditionalSwitch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Middleware for ConditionalSwitch {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let middleware = self
+            .arms
+            .iter()
+            .find(|(condition, _)| condition.evaluate(&req, extensions))
+            .map_or(&self.default, |(_, middleware)| middleware);
+
+        middleware.handle(req, extensions, next).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{CheckMiddleware, End};
+
+    #[tokio::test]
+    async fn test_first_match_wins() {
+        let first = CheckMiddleware::new();
+        let first_test = first.checker();
+        let second = CheckMiddleware::new();
+        let second_test = second.checker();
+        let default = CheckMiddleware::new();
+        let default_test = default.checker();
+
+        let switch = ConditionalSwitch::new()
+            .when(|req: &Request| req.method() == http::Method::GET, first)
+            .when(|_req: &Request| true, second)
+            .otherwise(default);
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(switch)
+                .with(End)
+                .build();
+
+        let resp = client.get("http://localhost").send().await.unwrap();
+
+        assert_eq!("end", resp.text().await.unwrap());
+        assert!(*first_test.lock().unwrap());
+        assert!(!*second_test.lock().unwrap());
+        assert!(!*default_test.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_runs_default_when_nothing_matches() {
+        let arm = CheckMiddleware::new();
+        let arm_test = arm.checker();
+        let default = CheckMiddleware::new();
+        let default_test = default.checker();
+
+        let switch = ConditionalSwitch::new()
+            .when(|_req: &Request| false, arm)
+            .otherwise(default);
+
+        let client =
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::builder().build().unwrap())
+                .with(switch)
+                .with(End)
+                .build();
+
+        client.get("http://localhost").send().await.unwrap();
+
+        assert!(!*arm_test.lock().unwrap());
+        assert!(*default_test.lock().unwrap());
+    }
+}
@@ -0,0 +1,227 @@
+use http::Extensions;
+use reqwest::Request;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+// The state of a toggle is a single word, so that every change is one atomic operation
+const DISABLED: u64 = 0;
+const ENABLED: u64 = u64::MAX;
+
+#[derive(Debug)]
+struct State {
+    /// [`DISABLED`], [`ENABLED`], or else the end of a temporary enable in nanoseconds since
+    /// `origin`
+    word: AtomicU64,
+    origin: Instant,
+}
+
+impl State {
+    fn new(enabled: bool) -> Self {
+        Self {
+            word: AtomicU64::new(if enabled { ENABLED } else { DISABLED }),
+            origin: Instant::now(),
+        }
+    }
+
+    fn is_enabled_in(&self, word: u64) -> bool {
+        match word {
+            DISABLED => false,
+            ENABLED => true,
+            // Only read the clock while a temporary enable is pending
+            until => self.origin.elapsed().as_nanos() < u128::from(until),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.is_enabled_in(self.word.load(Ordering::Relaxed))
+    }
+}
+
+/// A [`Condition`] backed by an atomic flag that can be flipped at runtime through a
+/// [`ToggleHandle`], for instance from an admin endpoint or a signal handler. Evaluating it is
+/// a single atomic load unless it has been enabled temporarily with
+/// [`ToggleHandle::enable_for`]. Clones share the same flag.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, Toggle};
+/// # use reqwest_conditional_middleware::Passthrough as Retry;
+///
+/// let toggle = Toggle::new(true);
+/// let handle = toggle.handle();
+/// let conditional = ConditionalMiddleware::new(Retry, toggle);
+///
+/// // Later, from anywhere that holds the handle
+/// handle.disable();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Toggle {
+    state: Arc<State>,
+}
+
+impl_condition_ops!(Toggle);
+
+impl Toggle {
+    /// Creates a toggle that is initially `enabled`
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            state: Arc::new(State::new(enabled)),
+        }
+    }
+
+    /// Returns a handle that can be used to flip the toggle
+    pub fn handle(&self) -> ToggleHandle {
+        ToggleHandle {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Returns true if the toggle is currently enabled
+    pub fn is_enabled(&self) -> bool {
+        self.state.is_enabled()
+    }
+}
+
+impl Condition for Toggle {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        self.is_enabled()
+    }
+}
+
+/// A handle for flipping a [`Toggle`]. Handles are cheap to clone and all clones refer to the
+/// same flag. Changes are seen by requests evaluating the toggle after they are made.
+#[derive(Clone, Debug)]
+pub struct ToggleHandle {
+    state: Arc<State>,
+}
+
+impl ToggleHandle {
+    /// Sets whether the toggle is enabled, returning whether it was before. This ends any
+    /// temporary enable.
+    pub fn set(&self, enabled: bool) -> bool {
+        let word = if enabled { ENABLED } else { DISABLED };
+        let previous = self.state.word.swap(word, Ordering::Relaxed);
+        self.state.is_enabled_in(previous)
+    }
+
+    /// Enables the toggle
+    pub fn enable(&self) {
+        self.set(true);
+    }
+
+    /// Disables the toggle
+    pub fn disable(&self) {
+        self.set(false);
+    }
+
+    /// Enables the toggle for `duration`, after which it reverts to disabled on its own, for
+    /// time-boxed debugging sessions. Calling this again restarts the period, and any other
+    /// change to the toggle ends it. A toggle that is already enabled stays enabled.
+    ///
+    /// ```
+    /// use reqwest_conditional_middleware::Toggle;
+    /// use std::time::Duration;
+    ///
+    /// let verbose_logging = Toggle::new(false);
+    /// verbose_logging.handle().enable_for(Duration::from_secs(15 * 60));
+    /// assert!(verbose_logging.is_enabled());
+    /// ```
+    pub fn enable_for(&self, duration: Duration) {
+        let until = self.state.origin.elapsed().saturating_add(duration);
+        let until = u64::try_from(until.as_nanos())
+            .unwrap_or(u64::MAX)
+            .clamp(DISABLED + 1, ENABLED - 1);
+        let _ = self
+            .state
+            .word
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |word| {
+                (word != ENABLED).then_some(until)
+            });
+    }
+
+    /// Flips the toggle, returning whether it is now enabled
+    pub fn flip(&self) -> bool {
+        let previous = self
+            .state
+            .word
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |word| {
+                Some(if self.state.is_enabled_in(word) {
+                    DISABLED
+                } else {
+                    ENABLED
+                })
+            })
+            .unwrap_or_else(|word| word);
+        !self.state.is_enabled_in(previous)
+    }
+
+    /// Returns true if the toggle is currently enabled
+    pub fn is_enabled(&self) -> bool {
+        self.state.is_enabled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle() {
+        let extensions = Extensions::new();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let toggle = Toggle::new(false);
+        let handle = toggle.handle();
+        let clone = toggle.clone();
+
+        assert!(!toggle.evaluate(&req, &extensions));
+        handle.enable();
+        assert!(toggle.evaluate(&req, &extensions));
+        assert!(clone.evaluate(&req, &extensions));
+
+        assert!(!handle.flip());
+        assert!(!toggle.is_enabled());
+        assert!(!handle.set(true));
+        assert!(handle.clone().is_enabled());
+        handle.disable();
+        assert!(!clone.evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_enable_for() {
+        let toggle = Toggle::new(false);
+        let handle = toggle.handle();
+
+        handle.enable_for(Duration::from_millis(50));
+        assert!(toggle.is_enabled());
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!toggle.is_enabled());
+
+        handle.enable_for(Duration::from_secs(60));
+        assert!(!handle.flip());
+        assert!(!toggle.is_enabled());
+
+        handle.enable();
+        handle.enable_for(Duration::ZERO);
+        assert!(toggle.is_enabled());
+    }
+
+    #[test]
+    fn test_concurrent_flips() {
+        let toggle = Toggle::new(false);
+        let handle = toggle.handle();
+
+        // Every flip sees the state left by the previous one, so an even number of flips
+        // returns each state as often and leaves the toggle as it was
+        let enabled: usize = std::thread::scope(|scope| {
+            let flippers: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| (0..1000).filter(|_| handle.flip()).count()))
+                .collect();
+            flippers.into_iter().map(|f| f.join().unwrap()).sum()
+        });
+        assert_eq!(2000, enabled);
+        assert!(!toggle.is_enabled());
+    }
+}
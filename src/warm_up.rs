@@ -0,0 +1,74 @@
+use http::Extensions;
+use reqwest::Request;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] that is false for the first `n` requests it evaluates and true for every
+/// request after them, so that a middleware depending on lazily initialized shared state, such
+/// as a cache filled by the first responses, only starts running once that state is warm.
+/// Clones share the same counter.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, WarmUp};
+/// # use reqwest_conditional_middleware::Passthrough as Prefetch;
+///
+/// let conditional = ConditionalMiddleware::new(Prefetch, WarmUp::new(10));
+/// ```
+#[derive(Clone, Debug)]
+pub struct WarmUp {
+    n: u64,
+    count: Arc<AtomicU64>,
+}
+
+impl_condition_ops!(WarmUp);
+
+impl WarmUp {
+    /// Creates a condition passing the first `n` requests through before the middleware runs
+    pub fn new(n: u64) -> Self {
+        Self {
+            n,
+            count: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Returns true once the first `n` requests have been evaluated
+    pub fn is_warm(&self) -> bool {
+        self.count.load(Ordering::Relaxed) >= self.n
+    }
+}
+
+impl Condition for WarmUp {
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        // Stop counting once warm so that the counter never wraps around
+        self.is_warm() || self.count.fetch_add(1, Ordering::Relaxed) >= self.n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warm_up() {
+        let extensions = Extensions::new();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let condition = WarmUp::new(3);
+        let clone = condition.clone();
+
+        let outcomes: Vec<_> = (0..5)
+            .map(|i| {
+                let condition = if i % 2 == 0 { &condition } else { &clone };
+                condition.evaluate(&req, &extensions)
+            })
+            .collect();
+        assert_eq!(vec![false, false, false, true, true], outcomes);
+        assert!(condition.is_warm());
+
+        let cold = WarmUp::new(0);
+        assert!(cold.is_warm());
+        assert!(cold.evaluate(&req, &extensions));
+    }
+}
@@ -0,0 +1,110 @@
+use http::Extensions;
+use reqwest::Request;
+use tokio::sync::watch::Receiver;
+
+use crate::condition::impl_condition_ops;
+use crate::Condition;
+
+/// A [`Condition`] driven by a [`tokio::sync::watch`] channel, so that a control plane can push
+/// decisions to running clients by sending on the channel. Each evaluation reads the latest
+/// value sent; once the sender is dropped the last value keeps being used.
+///
+/// ```
+/// use reqwest_conditional_middleware::{ConditionalMiddleware, Watch};
+/// # use reqwest_conditional_middleware::Passthrough as Retry;
+///
+/// #[derive(Default)]
+/// struct Config {
+///     retries: bool,
+/// }
+///
+/// let (tx, rx) = tokio::sync::watch::channel(Config::default());
+/// let conditional =
+///     ConditionalMiddleware::new(Retry, Watch::with_projection(rx, |config: &Config| config.retries));
+///
+/// // Later, from the control plane
+/// tx.send(Config { retries: true }).unwrap();
+/// ```
+pub struct Watch<T, F = fn(&T) -> bool> {
+    receiver: Receiver<T>,
+    project: F,
+}
+
+impl_condition_ops!(Watch<T, F>);
+
+impl Watch<bool> {
+    /// Creates a condition that evaluates to the latest value sent on the channel
+    pub fn new(receiver: Receiver<bool>) -> Self {
+        Self {
+            receiver,
+            project: |enabled| *enabled,
+        }
+    }
+}
+
+impl<T, F> Watch<T, F>
+where
+    F: Fn(&T) -> bool,
+{
+    /// Creates a condition that evaluates `project` on the latest value sent on the channel.
+    /// The channel is locked for reading while `project` runs, so it should be quick.
+    pub fn with_projection(receiver: Receiver<T>, project: F) -> Self {
+        Self { receiver, project }
+    }
+}
+
+impl<T, F> Clone for Watch<T, F>
+where
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            receiver: self.receiver.clone(),
+            project: self.project.clone(),
+        }
+    }
+}
+
+impl<T, F> Condition for Watch<T, F>
+where
+    T: Send + Sync + 'static,
+    F: Fn(&T) -> bool + Send + Sync + 'static,
+{
+    fn evaluate(&self, _req: &Request, _extensions: &Extensions) -> bool {
+        (self.project)(&self.receiver.borrow())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::watch;
+
+    #[test]
+    fn test_watch() {
+        let extensions = Extensions::new();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let (tx, rx) = watch::channel(false);
+        let condition = Watch::new(rx);
+
+        assert!(!condition.evaluate(&req, &extensions));
+        tx.send(true).unwrap();
+        assert!(condition.evaluate(&req, &extensions));
+        drop(tx);
+        assert!(condition.clone().evaluate(&req, &extensions));
+    }
+
+    #[test]
+    fn test_watch_projection() {
+        let extensions = Extensions::new();
+        let req = Request::new(http::Method::GET, "http://localhost".parse().unwrap());
+        let (tx, rx) = watch::channel(vec!["localhost".to_owned()]);
+        let condition = Watch::with_projection(rx, |hosts: &Vec<String>| {
+            hosts.iter().any(|host| host == "localhost")
+        });
+
+        assert!(condition.evaluate(&req, &extensions));
+        tx.send(Vec::new()).unwrap();
+        assert!(!condition.evaluate(&req, &extensions));
+    }
+}
@@ -0,0 +1,55 @@
+//! Checks that the fast path of the built-in matchers does not allocate. This runs as its own
+//! test binary so that the counting allocator does not apply to any other test.
+
+use http::Extensions;
+use reqwest::Request;
+use reqwest_conditional_middleware::matchers::{has_header, path_prefix, Matcher, Methods};
+use reqwest_conditional_middleware::Condition;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts allocations made by each thread, so that the test harness's own threads do not
+/// disturb the count
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[test]
+fn test_fast_path_does_not_allocate() {
+    let extensions = Extensions::new();
+    let condition = Matcher::new()
+        .method(http::Method::GET)
+        .host("api.example.com")
+        .domain("example.com")
+        .path_prefix("/v1/my%20project")
+        .route("/v1/{project}/instances/{id}")
+        .when(!Methods::safe() | has_header("x-force"));
+
+    let prefix = path_prefix("/v1/my%20project");
+    let req = Request::new(
+        http::Method::GET,
+        "https://API.example.com/v1/my%20project/instances/1"
+            .parse()
+            .unwrap(),
+    );
+    let before = ALLOCATIONS.with(Cell::get);
+    assert!(!condition.evaluate(&req, &extensions));
+    assert!(prefix.evaluate(&req, &extensions));
+    assert_eq!(ALLOCATIONS.with(Cell::get), before);
+}